@@ -0,0 +1,37 @@
+//! Generates the C header for the `cdylib` build into `$OUT_DIR/simd_rs.h`
+//! with the `c-header` feature.
+
+fn main() {
+    println!("cargo:rerun-if-changed=src");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+
+    #[cfg(feature = "c-header")]
+    generate_header();
+}
+
+/// Only src/ffi.rs is parsed, so everything public in there and nothing else
+/// ends up in the header. The library builds without the header, so failures
+/// are only reported as warnings.
+#[cfg(feature = "c-header")]
+fn generate_header() {
+    let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+    let out_dir = std::env::var("OUT_DIR").unwrap();
+
+    let config = match cbindgen::Config::from_file(format!("{crate_dir}/cbindgen.toml")) {
+        Ok(config) => config,
+        Err(e) => {
+            println!("cargo:warning=failed to read cbindgen.toml: {e}");
+            return;
+        }
+    };
+    let bindings = cbindgen::Builder::new()
+        .with_src(format!("{crate_dir}/src/ffi.rs"))
+        .with_config(config)
+        .generate();
+    match bindings {
+        Ok(bindings) => {
+            bindings.write_to_file(format!("{out_dir}/simd_rs.h"));
+        }
+        Err(e) => println!("cargo:warning=failed to generate C header: {e}"),
+    }
+}
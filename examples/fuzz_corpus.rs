@@ -0,0 +1,45 @@
+//! Writes the seed corpus for the encoder fuzz targets in `fuzz/`.
+//!
+//! cargo run --example fuzz_corpus [fuzz/corpus]
+
+use simd_rs::rng;
+
+const TARGETS: &[&str] = &["to_ascii_or_hex", "to_ascii_or_hex_simd_v1", "to_ascii_or_hex_simd_v2"];
+
+fn main() -> std::io::Result<()> {
+    let root = std::env::args().nth(1).unwrap_or_else(|| "fuzz/corpus".into());
+
+    let mut seeds: Vec<(String, Vec<u8>)> = Vec::new();
+
+    // Same inputs as `check()` in the benchmark binary.
+    let all_bytes: Vec<u8> = (0..=255).collect();
+    seeds.push(("all_bytes".into(), all_bytes.clone()));
+    for start in [0x1e, 0x1f, 0x20, 0x7d] {
+        seeds.push((format!("all_bytes_from_{start:02x}"), all_bytes[start..].to_vec()));
+    }
+    seeds.push(("foo".into(), b"foo".to_vec()));
+    seeds.push(("control_then_ascii".into(), b"\x01\x02a".to_vec()));
+    seeds.push(("control_then_ascii_2".into(), b"\x0f\x10\x12a".to_vec()));
+    seeds.push(("control_then_space".into(), b"\x1e\x1f a".to_vec()));
+
+    // Same kind of data as `perf_test` generates, at a few sizes.
+    for len in [31, 32, 33, 64, 1024, 4096] {
+        let mut data = Vec::with_capacity(len);
+        while data.len() < len {
+            data.extend_from_slice(&rng::random_u64().to_le_bytes());
+        }
+        data.truncate(len);
+        seeds.push((format!("random_{len}"), data));
+    }
+
+    for target in TARGETS {
+        let dir = std::path::Path::new(&root).join(target);
+        std::fs::create_dir_all(&dir)?;
+        for (name, data) in &seeds {
+            std::fs::write(dir.join(name), data)?;
+        }
+    }
+
+    println!("wrote {} seeds for {} targets to {root}", seeds.len(), TARGETS.len());
+    Ok(())
+}
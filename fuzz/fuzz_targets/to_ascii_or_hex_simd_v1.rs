@@ -0,0 +1,7 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    simd_rs_fuzz::check_encoder(simd_rs::to_ascii_or_hex_simd_v1, data);
+});
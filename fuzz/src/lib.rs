@@ -0,0 +1,62 @@
+//! Checks shared by the encoder fuzz targets.
+//!
+//! Run with `cargo +nightly fuzz run <target>` from the repository root, the
+//! seed corpus in `fuzz/corpus` is regenerated with
+//! `cargo run --example fuzz_corpus`.
+
+/// Asserts that `encoder` appends the same output as the scalar
+/// [`simd_rs::to_ascii_or_hex`], that the output is valid UTF-8 and that it
+/// decodes back into `data`.
+pub fn check_encoder(encoder: fn(&[u8], &mut String), data: &[u8]) {
+    const PREFIX: &str = "prefix\\x00";
+
+    let mut expected = String::from(PREFIX);
+    simd_rs::to_ascii_or_hex(data, &mut expected);
+
+    let mut out = String::from(PREFIX);
+    encoder(data, &mut out);
+
+    // Some encoders build the string with `from_utf8_unchecked`, so check the
+    // bytes explicitly instead of trusting the type.
+    assert!(std::str::from_utf8(out.as_bytes()).is_ok(), "invalid utf-8: {:?}", out.as_bytes());
+    assert!(out.is_ascii(), "{out:?}");
+    assert_eq!(out, expected);
+
+    let escaped = &out[PREFIX.len()..];
+    assert_eq!(unescape(escaped).as_deref(), Some(data), "{escaped:?}");
+}
+
+/// Decodes the output of the `to_ascii_or_hex*` encoders. Returns `None` if
+/// `s` isn't something an encoder could have produced.
+pub fn unescape(s: &str) -> Option<Vec<u8>> {
+    let s = s.as_bytes();
+    let mut res = Vec::with_capacity(s.len());
+    let mut i = 0;
+    while i < s.len() {
+        match s[i] {
+            b'\\' => match *s.get(i + 1)? {
+                c @ (b'\\' | b'"') => {
+                    res.push(c);
+                    i += 2;
+                }
+                b'x' => {
+                    let hex = std::str::from_utf8(s.get(i + 2..i + 4)?).ok()?;
+                    let c = u8::from_str_radix(hex, 16).ok()?;
+                    // Printable bytes are never hex escaped.
+                    if c >= b' ' && c <= b'~' { return None; }
+                    res.push(c);
+                    i += 4;
+                }
+                _ => return None,
+            },
+            // Unescaped special characters are never produced.
+            b'"' => return None,
+            c if c >= b' ' && c <= b'~' => {
+                res.push(c);
+                i += 1;
+            }
+            _ => return None,
+        }
+    }
+    return Some(res);
+}
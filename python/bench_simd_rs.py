@@ -0,0 +1,49 @@
+"""Compares the simd-rs escaper with Python's own ``bytes.hex()`` and
+``repr()``, and ``find_non_ascii`` with a regex search.
+
+    cargo build --release && python3 python/bench_simd_rs.py
+"""
+
+import os
+import re
+import timeit
+
+import simd_rs
+
+NON_PRINTABLE = re.compile(rb"[^\x20-\x7e]")
+
+
+def regex_find_non_ascii(data):
+    m = NON_PRINTABLE.search(data)
+    return m.start() if m else len(data)
+
+
+def bench(name, func, n_bytes, number):
+    seconds = min(timeit.repeat(func, number=number, repeat=5)) / number
+    print(f"  {name:<28} {seconds * 1e6:10.1f} us  {n_bytes / seconds / 1e9:6.2f} GB/s")
+    return seconds
+
+
+def main():
+    for n_bytes in (1024, 64 * 1024, 1024 * 1024):
+        number = max(1, 64 * 1024 * 1024 // n_bytes // 16)
+        random_data = os.urandom(n_bytes)
+        text_data = bytes(b"abcdefghijklmnopqrstuvwxyz 0123456789"[i % 37] for i in range(n_bytes - 1)) + b"\xff"
+
+        print(f"{n_bytes} bytes, random:")
+        hex_time = bench("bytes.hex()", random_data.hex, n_bytes, number)
+        repr_time = bench("repr()", lambda: repr(random_data), n_bytes, number)
+        escape_time = bench("simd_rs.escape()", lambda: simd_rs.escape(random_data), n_bytes, number)
+        print(f"  speedup vs hex: {hex_time / escape_time:.2f}x, vs repr: {repr_time / escape_time:.2f}x")
+
+        print(f"{n_bytes} bytes, text:")
+        repr_time = bench("repr()", lambda: repr(text_data), n_bytes, number)
+        escape_time = bench("simd_rs.escape()", lambda: simd_rs.escape(text_data), n_bytes, number)
+        print(f"  speedup vs repr: {repr_time / escape_time:.2f}x")
+        regex_time = bench("re.search()", lambda: regex_find_non_ascii(text_data), n_bytes, number)
+        find_time = bench("simd_rs.find_non_ascii()", lambda: simd_rs.find_non_ascii(text_data), n_bytes, number)
+        print(f"  speedup vs re.search: {regex_time / find_time:.2f}x")
+
+
+if __name__ == "__main__":
+    main()
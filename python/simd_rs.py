@@ -0,0 +1,118 @@
+"""ctypes bindings for the C ABI of the simd-rs ``cdylib``.
+
+The shared library is looked up in ``$SIMD_RS_LIB``, then in
+``target/release`` and ``target/debug`` of the repository. Build it with
+``cargo build --release`` first.
+
+All functions accept ``bytes``, ``bytearray`` and ``memoryview`` (or anything
+else supporting the buffer protocol). Writable buffers and ``bytes`` are
+passed without copying, read-only buffers of other types are copied once.
+"""
+
+import ctypes
+import os
+
+__all__ = [
+    "find_ascii",
+    "find_non_ascii",
+    "find_backslash_or_double_quote",
+    "escape",
+]
+
+ABI_VERSION = 1
+
+SIMD_OK = 0
+SIMD_ERROR_BUFFER_TOO_SMALL = 1
+SIMD_ERROR_NULL_POINTER = 2
+
+_REPO_ROOT = os.path.dirname(os.path.dirname(os.path.abspath(__file__)))
+
+
+def _find_library():
+    path = os.environ.get("SIMD_RS_LIB")
+    if path:
+        return path
+    for profile in ("release", "debug"):
+        path = os.path.join(_REPO_ROOT, "target", profile, "libsimd_rs.so")
+        if os.path.exists(path):
+            return path
+    raise OSError("libsimd_rs.so not found, run `cargo build --release` or set SIMD_RS_LIB")
+
+
+def _load():
+    lib = ctypes.CDLL(_find_library())
+
+    lib.simd_abi_version.argtypes = []
+    lib.simd_abi_version.restype = ctypes.c_uint32
+    version = lib.simd_abi_version()
+    if version != ABI_VERSION:
+        raise OSError(f"unsupported simd-rs ABI version {version}, expected {ABI_VERSION}")
+
+    for name in ("simd_find_ascii", "simd_find_non_ascii", "simd_find_backslash_or_double_quote"):
+        func = getattr(lib, name)
+        func.argtypes = [ctypes.c_void_p, ctypes.c_size_t]
+        func.restype = ctypes.c_size_t
+
+    lib.simd_escaped_len_max.argtypes = [ctypes.c_size_t]
+    lib.simd_escaped_len_max.restype = ctypes.c_size_t
+
+    lib.simd_escape.argtypes = [
+        ctypes.c_void_p,
+        ctypes.c_size_t,
+        ctypes.c_void_p,
+        ctypes.c_size_t,
+        ctypes.POINTER(ctypes.c_size_t),
+    ]
+    lib.simd_escape.restype = ctypes.c_int32
+    return lib
+
+
+_lib = _load()
+
+
+def _as_pointer(data):
+    """Returns ``(pointer, length, keepalive)`` for a bytes-like object."""
+    if isinstance(data, bytes):
+        return ctypes.cast(ctypes.c_char_p(data), ctypes.c_void_p), len(data), data
+
+    view = memoryview(data).cast("B")
+    if not view.contiguous:
+        raise ValueError("buffer must be contiguous")
+    if len(view) == 0:
+        return None, 0, view
+    if view.readonly:
+        copy = view.tobytes()
+        return ctypes.cast(ctypes.c_char_p(copy), ctypes.c_void_p), len(copy), copy
+    array = (ctypes.c_char * len(view)).from_buffer(view)
+    return ctypes.addressof(array), len(view), array
+
+
+def find_ascii(data):
+    """Index of the first printable ASCII byte, or ``len(data)`` if none."""
+    ptr, length, _keepalive = _as_pointer(data)
+    return _lib.simd_find_ascii(ptr, length)
+
+
+def find_non_ascii(data):
+    """Index of the first byte which isn't printable ASCII, or ``len(data)``."""
+    ptr, length, _keepalive = _as_pointer(data)
+    return _lib.simd_find_non_ascii(ptr, length)
+
+
+def find_backslash_or_double_quote(data):
+    """Index of the first ``\\`` or ``"``, or ``len(data)`` if none."""
+    ptr, length, _keepalive = _as_pointer(data)
+    return _lib.simd_find_backslash_or_double_quote(ptr, length)
+
+
+def escape(data):
+    """Printable ASCII is kept as is with ``\\`` and ``"`` escaped by a
+    backslash, every other byte becomes ``\\xNN``. Returns a ``str``."""
+    ptr, length, _keepalive = _as_pointer(data)
+    cap = _lib.simd_escaped_len_max(length)
+    out = ctypes.create_string_buffer(cap)
+    written = ctypes.c_size_t(0)
+    rc = _lib.simd_escape(ptr, length, out, cap, ctypes.byref(written))
+    if rc != SIMD_OK:
+        raise RuntimeError(f"simd_escape failed with {rc}")
+    return ctypes.string_at(out, written.value).decode("ascii")
@@ -0,0 +1,81 @@
+"""Tests for the ctypes bindings, run with ``python3 -m unittest`` from this
+directory (or through ``cargo test --test python``)."""
+
+import os
+import random
+import unittest
+
+import simd_rs
+
+
+def is_printable(c):
+    return 0x20 <= c <= 0x7E
+
+
+def reference_escape(data):
+    out = []
+    for c in data:
+        if c in b'\\"':
+            out.append("\\" + chr(c))
+        elif is_printable(c):
+            out.append(chr(c))
+        else:
+            out.append(f"\\x{c:02x}")
+    return "".join(out)
+
+
+def first(data, predicate):
+    return next((i for i, c in enumerate(data) if predicate(c)), len(data))
+
+
+class FindTest(unittest.TestCase):
+    def check(self, data):
+        self.assertEqual(simd_rs.find_ascii(data), first(data, is_printable))
+        self.assertEqual(simd_rs.find_non_ascii(data), first(data, lambda c: not is_printable(c)))
+        self.assertEqual(simd_rs.find_backslash_or_double_quote(data), first(data, lambda c: c in b'\\"'))
+
+    def test_empty(self):
+        for data in (b"", bytearray(), memoryview(b"")):
+            self.assertEqual(simd_rs.find_ascii(data), 0)
+            self.assertEqual(simd_rs.find_non_ascii(data), 0)
+            self.assertEqual(simd_rs.find_backslash_or_double_quote(data), 0)
+
+    def test_all_bytes(self):
+        all_bytes = bytes(range(256))
+        for start in range(len(all_bytes)):
+            self.check(all_bytes[start:])
+
+    def test_random(self):
+        rng = random.Random(0)
+        for _ in range(500):
+            n = rng.randrange(200)
+            self.check(bytes(rng.choice(b"ab \x00\xff\\\"") for _ in range(n)))
+
+    def test_buffer_types(self):
+        data = b"\x00\x01 hello \"world\"\xff" * 5
+        for buffer in (data, bytearray(data), memoryview(data), memoryview(bytearray(data))):
+            self.check(buffer)
+        # A slice of a memoryview points into the middle of the buffer.
+        view = memoryview(bytearray(data))[3:40]
+        self.assertEqual(simd_rs.find_non_ascii(view), first(bytes(view), lambda c: not is_printable(c)))
+
+
+class EscapeTest(unittest.TestCase):
+    def test_examples(self):
+        self.assertEqual(simd_rs.escape(b""), "")
+        self.assertEqual(simd_rs.escape(b"foo"), "foo")
+        self.assertEqual(simd_rs.escape(b'a"b\\c\x00\x7f\xff'), 'a\\"b\\\\c\\x00\\x7f\\xff')
+
+    def test_all_bytes(self):
+        all_bytes = bytes(range(256))
+        self.assertEqual(simd_rs.escape(all_bytes), reference_escape(all_bytes))
+
+    def test_random(self):
+        for n in (1, 31, 32, 33, 1000, 4096):
+            data = os.urandom(n)
+            self.assertEqual(simd_rs.escape(data), reference_escape(data))
+            self.assertEqual(simd_rs.escape(memoryview(bytearray(data))), reference_escape(data))
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,255 @@
+//! Guessing whether a buffer is text, and in which encoding.
+//!
+//! Only the first [`SAMPLE_LEN`] bytes are looked at. The checks are done in
+//! this order, the first one which applies decides:
+//!
+//! 1. All bytes are printable ASCII: [`ContentKind::Ascii`]. This is a single
+//!    pass of [`find_non_ascii_simd`] and covers most of the cheap cases.
+//! 2. A byte order mark: `EF BB BF` is [`ContentKind::Utf8Text`] if the rest
+//!    is valid UTF-8, `FF FE` is [`ContentKind::Utf16Le`] and `FE FF` is
+//!    [`ContentKind::Utf16Be`] if the rest is valid UTF-16 and passes the
+//!    control character check of [`Rule::Ratio`]. With [`Rule::Git`] it may
+//!    not contain any of those control characters.
+//! 3. The [`Rule`] decides whether the sample is [`ContentKind::Binary`], see
+//!    its variants. [`Rule::Ratio`] also recognizes UTF-16 without a byte
+//!    order mark here.
+//! 4. Only 7-bit bytes: [`ContentKind::Ascii`].
+//! 5. Valid UTF-8, a multi-byte sequence cut off by the end of the sample is
+//!    fine: [`ContentKind::Utf8Text`].
+//! 6. Anything else is [`ContentKind::Latin1Likely`]: high bytes which aren't
+//!    UTF-8 are most often some single-byte encoding.
+//!
+//! The kind is only a guess at the encoding, [`ContentKind::Ascii`] and
+//! [`ContentKind::Utf8Text`] may still contain control bytes, e.g. terminal
+//! escape sequences. Whether a payload can be printed as is has to be decided
+//! with [`can_print_verbatim`], anything else is better shown through
+//! [`to_ascii_or_hex`].
+
+use crate::*;
+
+/// Number of bytes from the start of the buffer which are looked at, the same
+/// as in git.
+pub const SAMPLE_LEN: usize = 8000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ContentKind {
+    /// Only 7-bit bytes, control bytes included.
+    Ascii,
+    /// Valid UTF-8 with at least one multi-byte sequence.
+    Utf8Text,
+    /// UTF-16, little endian.
+    Utf16Le,
+    /// UTF-16, big endian.
+    Utf16Be,
+    /// Text with high bytes which aren't valid UTF-8.
+    Latin1Likely,
+    Binary,
+}
+
+impl ContentKind {
+    #[inline]
+    pub fn is_text(self) -> bool {
+        self != ContentKind::Binary
+    }
+}
+
+/// How [`classify_content_with`] tells binary data from text.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Rule {
+    /// Binary if the sample contains a NUL byte, the same as git's
+    /// `buffer_is_binary`. Cheap and predictable, but UTF-16 without a byte
+    /// order mark is binary, and so is anything with control bytes but no
+    /// NULs.
+    Git,
+    /// Binary if more than `max_binary_ratio` of the sample are control
+    /// bytes other than `\t`, `\n`, `\r`, `\x0c` and `\x1b`, or `\x7f`. If
+    /// the sample isn't UTF-8 the C1 controls `0x80..=0x9f` count too, they
+    /// don't occur in Latin-1 text.
+    ///
+    /// Before that the sample is checked for UTF-16 without a byte order
+    /// mark: text in mostly Latin script has a zero in every other byte, and
+    /// is valid UTF-16 with control characters counted the same way.
+    Ratio { max_binary_ratio: f64 },
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Rule::Ratio { max_binary_ratio: 0.1 }
+    }
+}
+
+/// Same as [`classify_content_with`] with [`Rule::default`].
+#[inline]
+pub fn classify_content(data: &[u8]) -> ContentKind {
+    classify_content_with(data, Rule::default())
+}
+
+/// Guesses what kind of content `data` is, see the [module docs](self) for
+/// the rules.
+pub fn classify_content_with(data: &[u8], rule: Rule) -> ContentKind {
+    let truncated = data.len() > SAMPLE_LEN;
+    let sample = &data[..data.len().min(SAMPLE_LEN)];
+
+    if find_non_ascii_simd(sample) == sample.len() {
+        return ContentKind::Ascii;
+    }
+
+    if let Some(rest) = sample.strip_prefix(b"\xef\xbb\xbf") {
+        if is_utf8(rest, truncated) {
+            return ContentKind::Utf8Text;
+        }
+    }
+    let bom_max_binary_ratio = match rule {
+        Rule::Git => 0.0,
+        Rule::Ratio { max_binary_ratio } => max_binary_ratio,
+    };
+    for (bom, kind) in [(b"\xff\xfe", ContentKind::Utf16Le), (b"\xfe\xff", ContentKind::Utf16Be)] {
+        if let Some(rest) = sample.strip_prefix(bom) {
+            if is_utf16_text(rest, kind, truncated, bom_max_binary_ratio) {
+                return kind;
+            }
+        }
+    }
+
+    let histogram;
+    match rule {
+        Rule::Git => {
+            if sample.contains(&0) {
+                return ContentKind::Binary;
+            }
+            if sample.is_ascii() {
+                return ContentKind::Ascii;
+            }
+            if is_utf8(sample, truncated) {
+                return ContentKind::Utf8Text;
+            }
+            return ContentKind::Latin1Likely;
+        }
+        Rule::Ratio { max_binary_ratio } => {
+            if let Some(kind) = guess_utf16(sample, truncated, max_binary_ratio) {
+                return kind;
+            }
+
+            histogram = byte_histogram(sample);
+            let max_binary = max_binary_ratio * sample.len() as f64;
+            let mut binary = 0;
+            for c in (0x00..0x20).chain([0x7f]) {
+                if is_binary_control(c) {
+                    binary += histogram[c as usize];
+                }
+            }
+            if binary as f64 > max_binary {
+                return ContentKind::Binary;
+            }
+
+            if histogram[0x80..].iter().all(|&n| n == 0) {
+                return ContentKind::Ascii;
+            }
+            if is_utf8(sample, truncated) {
+                return ContentKind::Utf8Text;
+            }
+
+            let c1_controls: u64 = histogram[0x80..=0x9f].iter().sum();
+            if (binary + c1_controls) as f64 > max_binary {
+                return ContentKind::Binary;
+            }
+            return ContentKind::Latin1Likely;
+        }
+    }
+}
+
+/// Returns `true` if `data` can be written to a terminal as is: it's valid
+/// UTF-8 without control characters other than `\t` and `\n`. Unlike
+/// [`classify_content`] this looks at all of `data`.
+///
+/// ```
+/// # use simd_rs::content::can_print_verbatim;
+/// assert!(can_print_verbatim("caf\u{e9}\tcr\u{e8}me\n".as_bytes()));
+/// assert!(!can_print_verbatim(b"\x1b]0;pwned\x07"));
+/// ```
+pub fn can_print_verbatim(data: &[u8]) -> bool {
+    if is_printable_ascii(data) {
+        return true;
+    }
+    let Ok(s) = core::str::from_utf8(data) else { return false };
+    // `is_control` covers the C1 controls too, e.g. the 8-bit CSI `U+009B`.
+    return s.chars().all(|c| !c.is_control() || c == '\t' || c == '\n');
+}
+
+/// Returns `true` for the ASCII control bytes which don't occur in text, i.e.
+/// all of them but `\t`, `\n`, `\r`, `\x0c` and `\x1b`.
+#[inline]
+fn is_binary_control(c: u8) -> bool {
+    (c < 0x20 || c == 0x7f) && !matches!(c, b'\t' | b'\n' | b'\r' | b'\x0c' | b'\x1b')
+}
+
+/// Returns `true` if `data` is valid UTF-8, except that it may end in the
+/// middle of a character if it's been `truncated`.
+fn is_utf8(data: &[u8], truncated: bool) -> bool {
+    match core::str::from_utf8(data) {
+        Ok(_) => true,
+        // `error_len` is `None` if the input ended in the middle of a
+        // character.
+        Err(e) => truncated && e.error_len().is_none(),
+    }
+}
+
+/// Returns `true` if `data` is valid UTF-16 in the byte order of `kind`,
+/// except that it may end in the middle of a character if it's been
+/// `truncated`, and at most `max_binary_ratio` of its characters are control
+/// characters which don't occur in text, C1 controls included.
+fn is_utf16_text(data: &[u8], kind: ContentKind, truncated: bool, max_binary_ratio: f64) -> bool {
+    let mut data = data;
+    if !data.len().is_multiple_of(2) {
+        if !truncated {
+            return false;
+        }
+        data = &data[..data.len() - 1];
+    }
+    let unit = |c: &[u8]| match kind {
+        ContentKind::Utf16Be => u16::from_be_bytes([c[0], c[1]]),
+        _ => u16::from_le_bytes([c[0], c[1]]),
+    };
+    if truncated && data.len() >= 2 && (0xd800..0xdc00).contains(&unit(&data[data.len() - 2..])) {
+        // A high surrogate cut off from its pair by the end of the sample.
+        data = &data[..data.len() - 2];
+    }
+
+    let mut binary = 0;
+    for c in char::decode_utf16(data.chunks_exact(2).map(unit)) {
+        let Ok(c) = c else { return false };
+        let c = c as u32;
+        binary += (c < 0x100 && (is_binary_control(c as u8) || (0x80..=0x9f).contains(&c))) as usize;
+    }
+    return binary as f64 <= max_binary_ratio * (data.len() / 2) as f64;
+}
+
+/// Recognizes UTF-16 by the zeros in the high bytes of Latin characters,
+/// which have to be in the same half of at least 90% of the code units and
+/// in the other half of less than 10% of them. It also has to pass
+/// [`is_utf16_text`], otherwise arrays of small integers would look like
+/// text.
+fn guess_utf16(data: &[u8], truncated: bool, max_binary_ratio: f64) -> Option<ContentKind> {
+    let units = data.len() / 2;
+    if units < 2 {
+        return None;
+    }
+    let mut zeros = [0_usize; 2];
+    for unit in data.chunks_exact(2) {
+        zeros[0] += (unit[0] == 0) as usize;
+        zeros[1] += (unit[1] == 0) as usize;
+    }
+    let most = |n: usize| n * 10 >= units * 9;
+    let few = |n: usize| n * 10 < units;
+    let kind = if most(zeros[1]) && few(zeros[0]) {
+        ContentKind::Utf16Le
+    } else if most(zeros[0]) && few(zeros[1]) {
+        ContentKind::Utf16Be
+    } else {
+        return None;
+    };
+    if !is_utf16_text(data, kind, truncated, max_binary_ratio) {
+        return None;
+    }
+    return Some(kind);
+}
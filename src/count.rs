@@ -0,0 +1,68 @@
+//! Counting the bytes of a class without encoding anything.
+
+use crate::*;
+
+/// Returns the number of printable ASCII bytes in `data`.
+#[no_mangle]
+pub fn count_printable(data: &[u8]) -> usize {
+    if has_avx2() {
+        // SAFETY: AVX2 support was checked right above.
+        unsafe { kernels::count_printable(data) }
+    } else {
+        count_printable_non_simd(data)
+    }
+}
+
+/// Returns the number of bytes in `data` which aren't printable ASCII, each
+/// of them takes 4 bytes in the output of the encoders.
+#[inline]
+pub fn count_non_printable(data: &[u8]) -> usize {
+    data.len() - count_printable(data)
+}
+
+/// Returns the number of `\` and `"` bytes in `data`.
+#[no_mangle]
+pub fn count_backslash_or_double_quote(data: &[u8]) -> usize {
+    if has_avx2() {
+        // SAFETY: AVX2 support was checked right above.
+        unsafe { kernels::count_backslash_or_double_quote(data) }
+    } else {
+        count_backslash_or_double_quote_non_simd(data)
+    }
+}
+
+#[no_mangle]
+#[inline(never)]
+pub fn count_printable_non_simd(data: &[u8]) -> usize {
+    let mut count = 0;
+    for &c in data {
+        if c >= b' ' && c <= b'~' {
+            count += 1;
+        }
+    }
+    return count;
+}
+
+#[no_mangle]
+#[inline(never)]
+pub fn count_non_printable_non_simd(data: &[u8]) -> usize {
+    let mut count = 0;
+    for &c in data {
+        if c < b' ' || c > b'~' {
+            count += 1;
+        }
+    }
+    return count;
+}
+
+#[no_mangle]
+#[inline(never)]
+pub fn count_backslash_or_double_quote_non_simd(data: &[u8]) -> usize {
+    let mut count = 0;
+    for &c in data {
+        if c == b'\\' || c == b'"' {
+            count += 1;
+        }
+    }
+    return count;
+}
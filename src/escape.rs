@@ -0,0 +1,326 @@
+//! Escaping without spare copies: into exactly sized output, shared by
+//! [`escape_cow`] and [`ffi::simd_escape`], or into segments which borrow the
+//! verbatim parts of the input, see [`escaped_segments`].
+//!
+//! The output is the same as that of [`to_ascii_or_hex`].
+
+use crate::*;
+use alloc::borrow::Cow;
+
+/// Returns the escaped form of `data`, borrowing it if it doesn't need
+/// escaping.
+///
+/// Clean input costs a single scan with [`needs_escaping`]. Otherwise the
+/// exact output length is counted first, so the returned string is allocated
+/// once and has no spare capacity.
+///
+/// ```
+/// # use simd_rs::escape_cow;
+/// # use std::borrow::Cow;
+/// assert!(matches!(escape_cow(b"GET /index.html"), Cow::Borrowed("GET /index.html")));
+/// assert_eq!(escape_cow(b"say \"hi\"\n"), r#"say \"hi\"\x0a"#);
+/// ```
+pub fn escape_cow(data: &[u8]) -> Cow<'_, str> {
+    if let Some(s) = as_unescaped_str(data) {
+        return Cow::Borrowed(s);
+    }
+
+    let len = escaped_len(data);
+    let mut buffer = alloc::vec![0; len];
+    let mut writer = SliceWriter { out: &mut buffer, len: 0 };
+    // `as_unescaped_str` has already run `needs_escaping`.
+    escape_with(data, &mut writer);
+    debug_assert_eq!(writer.len, len);
+
+    // The escaped output only consists of printable ASCII bytes.
+    debug_assert!(core::str::from_utf8(&buffer).is_ok(), "{buffer:?}");
+    // SAFETY: see above.
+    return Cow::Owned(unsafe { String::from_utf8_unchecked(buffer) });
+}
+
+/// Returns the exact length of the escaped form of `data`: every byte which
+/// isn't printable ASCII takes 4 bytes, every `\` and `"` takes 2.
+#[inline]
+pub fn escaped_len(data: &[u8]) -> usize {
+    data.len() + 3 * count_non_printable(data) + count_backslash_or_double_quote(data)
+}
+
+/// Receives the escaped output from [`escape_with`] piece by piece.
+pub(crate) trait EscapeSink {
+    /// Bytes of the input which are their own escaped form.
+    fn push_verbatim(&mut self, bytes: &[u8]);
+    /// Bytes which aren't printable ASCII, each escaped as `\xNN`.
+    fn push_hex(&mut self, bytes: &[u8]);
+    /// A single `\` or `"`, escaped with a backslash.
+    fn push_special(&mut self, c: u8);
+}
+
+/// Returns the `\xNN` escape of `c`.
+#[inline(always)]
+pub(crate) fn hex_escape(c: u8) -> [u8; 4] {
+    [b'\\', b'x', HEX_DIGIT[(c >> 4) as usize], HEX_DIGIT[(c & 0xf) as usize]]
+}
+
+/// The run splitting shared by all the SIMD encoders: alternating runs of
+/// bytes which aren't printable ASCII and of printable ones are found with
+/// [`find_ascii_simd`] and [`find_non_ascii_simd`], then the `\` and `"` in
+/// the longer printable runs with [`match_indices`], which only loads each
+/// block once. Verbatim bytes between them are passed to `sink` in one piece.
+pub(crate) fn escape_with(data: &[u8], sink: &mut impl EscapeSink) {
+    let mut tail = data;
+    while !tail.is_empty() {
+        let (ascii_piece, non_ascii_piece);
+
+        let i = find_ascii_simd(tail);
+        (non_ascii_piece, tail) = tail.split_at(i);
+        if !non_ascii_piece.is_empty() {
+            sink.push_hex(non_ascii_piece);
+        }
+
+        if tail.is_empty() { break; }
+
+        let i = find_non_ascii_simd(tail);
+        (ascii_piece, tail) = tail.split_at(i);
+
+        // Setting up `match_indices` costs more than a scalar scan of a
+        // piece shorter than a vector, and those are common in binary data.
+        if ascii_piece.len() < VECTOR_SIZE {
+            let is_special = |(_, &c): &(usize, &u8)| c == b'\\' || c == b'"';
+            let specials = ascii_piece.iter().enumerate().filter(is_special).map(|(i, _)| i);
+            escape_printable(ascii_piece, specials, sink);
+        } else {
+            escape_printable(ascii_piece, match_indices(ascii_piece, ByteClass::BackslashOrDoubleQuote), sink);
+        }
+    }
+}
+
+/// Passes the printable `piece` to `sink`, with the `\` and `"` at `specials`
+/// escaped.
+#[inline(always)]
+fn escape_printable(piece: &[u8], specials: impl Iterator<Item = usize>, sink: &mut impl EscapeSink) {
+    let mut verbatim_start = 0;
+    for i in specials {
+        if i > verbatim_start {
+            sink.push_verbatim(&piece[verbatim_start..i]);
+        }
+        sink.push_special(piece[i]);
+        verbatim_start = i + 1;
+    }
+    if verbatim_start < piece.len() {
+        sink.push_verbatim(&piece[verbatim_start..]);
+    }
+}
+
+impl EscapeSink for alloc::vec::Vec<u8> {
+    #[inline(always)]
+    fn push_verbatim(&mut self, bytes: &[u8]) {
+        self.extend_from_slice(bytes);
+    }
+
+    #[inline(always)]
+    fn push_hex(&mut self, bytes: &[u8]) {
+        for &c in bytes {
+            self.extend_from_slice(&hex_escape(c));
+        }
+    }
+
+    #[inline(always)]
+    fn push_special(&mut self, c: u8) {
+        self.extend_from_slice(&[b'\\', c]);
+    }
+}
+
+/// Writes into a fixed buffer, keeps counting the length once it's full.
+pub(crate) struct SliceWriter<'a> {
+    pub(crate) out: &'a mut [u8],
+    pub(crate) len: usize,
+}
+
+impl SliceWriter<'_> {
+    #[inline(always)]
+    fn push(&mut self, bytes: &[u8]) {
+        if let Some(dst) = self.out.get_mut(self.len..self.len + bytes.len()) {
+            dst.copy_from_slice(bytes);
+        }
+        self.len += bytes.len();
+    }
+}
+
+impl EscapeSink for SliceWriter<'_> {
+    #[inline(always)]
+    fn push_verbatim(&mut self, bytes: &[u8]) {
+        self.push(bytes);
+    }
+
+    #[inline(always)]
+    fn push_hex(&mut self, bytes: &[u8]) {
+        for &c in bytes {
+            self.push(&hex_escape(c));
+        }
+    }
+
+    #[inline(always)]
+    fn push_special(&mut self, c: u8) {
+        self.push(&[b'\\', c]);
+    }
+}
+
+/// [`escape_with`] into a fixed buffer, data which doesn't need escaping at
+/// all is copied in one go.
+pub(crate) fn escape_into(data: &[u8], out: &mut SliceWriter) {
+    if !needs_escaping(data) {
+        out.push(data);
+        return;
+    }
+    escape_with(data, out);
+}
+
+/// Returns an iterator over the escaped form of `data` in pieces, most of
+/// which borrow from `data`, see [`Segment`].
+///
+/// The input is split into runs the same way as in
+/// [`to_ascii_or_hex_simd_v2`]. A `\` or `"` is escaped by a separate
+/// one-byte segment holding just the backslash, the byte itself starts the
+/// next verbatim segment. Segments are never empty.
+///
+/// ```
+/// # use simd_rs::escape::{escaped_segments, Segment};
+/// let segments: Vec<_> = escaped_segments(b"say \"hi\"\n").map(|s| s.as_bytes().to_vec()).collect();
+/// assert_eq!(segments, [&b"say "[..], b"\\", b"\"hi", b"\\", b"\"", b"\\x0a"]);
+/// ```
+#[inline]
+pub fn escaped_segments(data: &[u8]) -> EscapedSegments<'_> {
+    EscapedSegments { tail: data, ascii: &[], escaped_first: false }
+}
+
+/// A piece of the output of [`escaped_segments`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Segment<'a> {
+    /// Part of the input which is its own escaped form, or the backslash
+    /// escaping the `\` or `"` at the start of the next one.
+    Verbatim(&'a [u8]),
+    /// Bytes which aren't printable ASCII, escaped as `\xNN`.
+    Hex(HexChunk),
+}
+
+impl<'a> Segment<'a> {
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            Segment::Verbatim(s) => s,
+            Segment::Hex(chunk) => chunk.as_bytes(),
+        }
+    }
+}
+
+/// Up to [`HexChunk::MAX_BYTES`] consecutive input bytes escaped as `\xNN`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HexChunk {
+    buf: [u8; 4 * HexChunk::MAX_BYTES],
+    len: u8,
+}
+
+impl HexChunk {
+    /// Longer runs of bytes which aren't printable ASCII are split into
+    /// several chunks.
+    pub const MAX_BYTES: usize = 16;
+
+    fn new(data: &[u8]) -> Self {
+        debug_assert!(data.len() <= Self::MAX_BYTES);
+        let mut res = Self { buf: [0; 4 * Self::MAX_BYTES], len: 0 };
+        for (&c, dst) in data.iter().zip(res.buf.chunks_exact_mut(4)) {
+            dst.copy_from_slice(&hex_escape(c));
+        }
+        res.len = (4 * data.len()) as u8;
+        return res;
+    }
+
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf[..self.len as usize]
+    }
+}
+
+/// Iterator returned by [`escaped_segments`].
+#[derive(Clone, Debug)]
+pub struct EscapedSegments<'a> {
+    /// Input which hasn't been split into runs yet.
+    tail: &'a [u8],
+    /// Rest of the current run of printable ASCII.
+    ascii: &'a [u8],
+    /// The backslash for the first byte of `ascii` has been yielded already.
+    escaped_first: bool,
+}
+
+impl<'a> Iterator for EscapedSegments<'a> {
+    type Item = Segment<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(&first) = self.ascii.first() {
+            let special = matches!(first, b'\\' | b'"');
+            if special && !self.escaped_first {
+                self.escaped_first = true;
+                return Some(Segment::Verbatim(b"\\"));
+            }
+            let skip = special as usize;
+            let i = skip + find_backslash_or_double_quote_simd(&self.ascii[skip..]);
+            let verbatim;
+            (verbatim, self.ascii) = self.ascii.split_at(i);
+            self.escaped_first = false;
+            return Some(Segment::Verbatim(verbatim));
+        }
+
+        let &first = self.tail.first()?;
+        if RunKind::of(first) == RunKind::NonPrintable {
+            let i = find_ascii_simd(self.tail).min(HexChunk::MAX_BYTES);
+            let non_ascii;
+            (non_ascii, self.tail) = self.tail.split_at(i);
+            return Some(Segment::Hex(HexChunk::new(non_ascii)));
+        }
+
+        let i = find_non_ascii_simd(self.tail);
+        (self.ascii, self.tail) = self.tail.split_at(i);
+        return self.next();
+    }
+}
+
+impl core::iter::FusedIterator for EscapedSegments<'_> {}
+
+/// Writes the escaped form of `data` into `out` with
+/// [`std::io::Write::write_vectored`], without copying the verbatim parts.
+///
+/// Segments are passed in batches of up to 64. Short writes are retried and
+/// [`std::io::ErrorKind::Interrupted`] is ignored, same as in
+/// [`std::io::Write::write_all`].
+#[cfg(feature = "std")]
+pub fn write_escaped<W: std::io::Write + ?Sized>(out: &mut W, data: &[u8]) -> std::io::Result<()> {
+    use std::io::IoSlice;
+    const BATCH: usize = 64;
+
+    let mut segments = escaped_segments(data);
+    let mut batch = [Segment::Verbatim(&[]); BATCH];
+    loop {
+        let mut n = 0;
+        for segment in segments.by_ref().take(BATCH) {
+            batch[n] = segment;
+            n += 1;
+        }
+        if n == 0 {
+            return Ok(());
+        }
+
+        let mut io_slices = [IoSlice::new(&[]); BATCH];
+        for (io_slice, segment) in io_slices.iter_mut().zip(&batch[..n]) {
+            *io_slice = IoSlice::new(segment.as_bytes());
+        }
+        let mut io_slices = &mut io_slices[..n];
+        while !io_slices.is_empty() {
+            match out.write_vectored(io_slices) {
+                Ok(0) => return Err(std::io::ErrorKind::WriteZero.into()),
+                Ok(written) => IoSlice::advance_slices(&mut io_slices, written),
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
@@ -0,0 +1,109 @@
+//! C ABI for the `cdylib` build.
+//!
+//! The matching header is generated by `build.rs` into `$OUT_DIR/simd_rs.h`
+//! with the `c-header` feature.
+//! All functions accept a null `data` pointer if `len` is 0.
+
+use crate::*;
+use crate::escape::{escape_into, SliceWriter};
+
+/// Version of this C ABI, bumped on every incompatible change. Bindings should
+/// check it against [`simd_abi_version`] before calling anything else.
+pub const SIMD_ABI_VERSION: u32 = 1;
+
+/// The call succeeded.
+pub const SIMD_OK: i32 = 0;
+/// The output buffer is too small, `*written` is set to the required size.
+pub const SIMD_ERROR_BUFFER_TOO_SMALL: i32 = 1;
+/// A required pointer argument was null.
+pub const SIMD_ERROR_NULL_POINTER: i32 = 2;
+
+/// Returns [`SIMD_ABI_VERSION`] of the loaded library.
+#[no_mangle]
+pub extern "C" fn simd_abi_version() -> u32 {
+    SIMD_ABI_VERSION
+}
+
+/// # Safety
+/// `data` must be valid for reads of `len` bytes, or `len` must be 0.
+#[inline(always)]
+unsafe fn as_slice<'a>(data: *const u8, len: usize) -> &'a [u8] {
+    if len == 0 {
+        return &[];
+    }
+    core::slice::from_raw_parts(data, len)
+}
+
+/// Returns the index of the first printable ASCII byte in `data`, or `len` if
+/// there is none.
+///
+/// # Safety
+/// `data` must be valid for reads of `len` bytes, or `len` must be 0.
+#[no_mangle]
+pub unsafe extern "C" fn simd_find_ascii(data: *const u8, len: usize) -> usize {
+    find_ascii_simd(as_slice(data, len))
+}
+
+/// Returns the index of the first byte in `data` which isn't printable ASCII,
+/// or `len` if there is none.
+///
+/// # Safety
+/// `data` must be valid for reads of `len` bytes, or `len` must be 0.
+#[no_mangle]
+pub unsafe extern "C" fn simd_find_non_ascii(data: *const u8, len: usize) -> usize {
+    find_non_ascii_simd(as_slice(data, len))
+}
+
+/// Returns the index of the first `\` or `"` in `data`, or `len` if there is
+/// none.
+///
+/// # Safety
+/// `data` must be valid for reads of `len` bytes, or `len` must be 0.
+#[no_mangle]
+pub unsafe extern "C" fn simd_find_backslash_or_double_quote(data: *const u8, len: usize) -> usize {
+    find_backslash_or_double_quote_simd(as_slice(data, len))
+}
+
+/// Returns the maximum number of bytes [`simd_escape`] can write for an
+/// input of `len` bytes.
+#[no_mangle]
+pub extern "C" fn simd_escaped_len_max(len: usize) -> usize {
+    len.saturating_mul(4)
+}
+
+/// Escapes `data` the same way as [`to_ascii_or_hex`] into `out`, the output
+/// is not null-terminated.
+///
+/// Returns [`SIMD_OK`] and sets `*written` to the number of bytes written on
+/// success. If the output doesn't fit into `out_cap` bytes returns
+/// [`SIMD_ERROR_BUFFER_TOO_SMALL`] and sets `*written` to the required size,
+/// in which case the contents of `out` are unspecified.
+///
+/// # Safety
+/// `data` must be valid for reads of `len` bytes, or `len` must be 0.
+/// `out` must be valid for writes of `out_cap` bytes, or `out_cap` must be 0.
+/// `written` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn simd_escape(
+    data: *const u8,
+    len: usize,
+    out: *mut u8,
+    out_cap: usize,
+    written: *mut usize,
+) -> i32 {
+    if written.is_null() || (data.is_null() && len != 0) || (out.is_null() && out_cap != 0) {
+        return SIMD_ERROR_NULL_POINTER;
+    }
+
+    let data = as_slice(data, len);
+    let out: &mut [u8] = if out_cap == 0 { &mut [] } else { core::slice::from_raw_parts_mut(out, out_cap) };
+
+    let mut writer = SliceWriter { out, len: 0 };
+    escape_into(data, &mut writer);
+
+    *written = writer.len;
+    if writer.len > out_cap {
+        return SIMD_ERROR_BUFFER_TOO_SMALL;
+    }
+    return SIMD_OK;
+}
@@ -0,0 +1,83 @@
+//! Byte histograms and the statistics built on top of them.
+
+/// Returns the number of occurrences of each byte value in `data`.
+///
+/// Counting into a single table stalls on store-to-load forwarding whenever
+/// the same value repeats, which is the common case in text and zero-filled
+/// binaries: each increment has to wait for the previous one. Instead the
+/// input is read 8 bytes at a time and spread over 4 tables, which are merged
+/// at the end. Scatter-increments don't map onto AVX2, so the loop itself is
+/// scalar, only the merge is vectorized by the compiler.
+#[no_mangle]
+pub fn byte_histogram(data: &[u8]) -> [u64; 256] {
+    let mut res = [0_u64; 256];
+
+    // `u32` counters keep the tables within 4 KiB. No counter can exceed the
+    // length of the chunk, so chunks of `u32::MAX` bytes can't overflow them.
+    for chunk in data.chunks(u32::MAX as usize) {
+        let mut tables = [[0_u32; 256]; 4];
+
+        let mut words = chunk.chunks_exact(8);
+        for word in &mut words {
+            let w = u64::from_le_bytes(word.try_into().unwrap());
+            tables[0][(w & 0xff) as usize] += 1;
+            tables[1][(w >> 8 & 0xff) as usize] += 1;
+            tables[2][(w >> 16 & 0xff) as usize] += 1;
+            tables[3][(w >> 24 & 0xff) as usize] += 1;
+            tables[0][(w >> 32 & 0xff) as usize] += 1;
+            tables[1][(w >> 40 & 0xff) as usize] += 1;
+            tables[2][(w >> 48 & 0xff) as usize] += 1;
+            tables[3][(w >> 56) as usize] += 1;
+        }
+        for &c in words.remainder() {
+            tables[0][c as usize] += 1;
+        }
+
+        for i in 0..256 {
+            res[i] += tables[0][i] as u64 + tables[1][i] as u64 + tables[2][i] as u64 + tables[3][i] as u64;
+        }
+    }
+
+    return res;
+}
+
+#[no_mangle]
+#[inline(never)]
+pub fn byte_histogram_non_simd(data: &[u8]) -> [u64; 256] {
+    let mut res = [0_u64; 256];
+    for &c in data {
+        res[c as usize] += 1;
+    }
+    return res;
+}
+
+/// Returns the fraction of printable ASCII bytes, or 1 for an empty
+/// histogram, which doesn't contain anything non-printable.
+pub fn printable_ratio(histogram: &[u64; 256]) -> f64 {
+    let total: u64 = histogram.iter().sum();
+    if total == 0 {
+        return 1.0;
+    }
+    let printable: u64 = histogram[b' ' as usize..=b'~' as usize].iter().sum();
+    return printable as f64 / total as f64;
+}
+
+/// Returns the Shannon entropy in bits per byte: 0 if only a single value
+/// occurs (or the histogram is empty), 8 if all values are equally likely.
+///
+/// Compressed or encrypted data is close to 8, text is usually below 5.
+#[cfg(feature = "std")]
+pub fn shannon_entropy(histogram: &[u64; 256]) -> f64 {
+    let total: u64 = histogram.iter().sum();
+    if total == 0 {
+        return 0.0;
+    }
+    let total = total as f64;
+    let mut entropy = 0.0;
+    for &count in histogram {
+        if count == 0 { continue; }
+        let p = count as f64 / total;
+        entropy -= p * p.log2();
+    }
+    return entropy;
+}
@@ -0,0 +1,469 @@
+//! AVX2 kernels behind the safe `find_*_simd` functions in the crate root.
+//!
+//! Every kernel is an `unsafe fn` compiled with `#[target_feature(enable =
+//! "avx2")]`, calling one is only sound after checking that the CPU supports
+//! AVX2, see [`crate::has_avx2`].
+//!
+//! Apart from that the kernels uphold the following invariants, which is what
+//! makes the raw pointer arithmetic in them sound:
+//! - the vector loop only runs while more than [`VECTOR_SIZE`] bytes are left,
+//!   so every unaligned 32-byte load is within `data`;
+//! - the scalar tail loop only dereferences `p` while `p < range.end`;
+//! - the returned index is either the offset of a byte within `data` or
+//!   `data.len()`, so it can always be passed to `split_at`.
+//!
+//! The `count_*` kernels only load a vector while at least [`VECTOR_SIZE`]
+//! bytes are left and flush their per-lane byte counters before they can
+//! wrap around.
+//!
+//! The predicate kernels (`is_printable_ascii`, `needs_escaping`) load
+//! [`UNROLL`] vectors at a time while at least that many are left, then single
+//! vectors while at least one is left, same as the `count_*` kernels.
+//!
+//! The `rfind_*` kernels are the same loops run backwards: the vector loop
+//! only steps back while more than [`VECTOR_SIZE`] bytes are left before `p`,
+//! the tail loop only dereferences `p - 1` while `p > range.start`, and the
+//! returned index is one past a byte within `data` or 0.
+//!
+//! Under Miri or with the `force-scalar` feature the intrinsics are replaced
+//! with the portable versions from [`crate::portable`] and the kernels don't
+//! require any target features.
+
+#[cfg(not(any(miri, feature = "force-scalar")))]
+use core::arch::x86_64::*;
+#[cfg(any(miri, feature = "force-scalar"))]
+use crate::portable::*;
+use core::mem::*;
+
+pub const ASCII_END:    __m256i = unsafe { transmute([b'~' + 1; 32]) };
+pub const ASCII_START:  __m256i = unsafe { transmute([b' ' - 1; 32]) };
+pub const BACKSLASH:    __m256i = unsafe { transmute([b'\\'; 32]) };
+pub const DOUBLE_QUOTE: __m256i = unsafe { transmute([b'"'; 32]) };
+
+pub const VECTOR_SIZE: usize = core::mem::size_of::<__m256i>();
+
+/// AVX2 version of [`crate::find_ascii_non_simd`].
+///
+/// # Safety
+/// The CPU must support AVX2.
+#[cfg_attr(not(any(miri, feature = "force-scalar")), target_feature(enable = "avx2"))]
+pub unsafe fn find_ascii(data: &[u8]) -> usize {
+    let range = data.as_ptr_range();
+    let mut p = range.start;
+
+    unsafe {
+        while range.end.offset_from(p) as usize > VECTOR_SIZE {
+            let v = _mm256_loadu_si256(p as _);
+
+            let lower_bound = _mm256_cmpgt_epi8(v, ASCII_START);
+            let upper_bound = _mm256_cmpgt_epi8(ASCII_END, v);
+            let in_bounds = _mm256_and_si256(lower_bound, upper_bound);
+            let mask = _mm256_movemask_epi8(in_bounds);
+            let offset = mask.trailing_zeros();
+
+            if mask != 0 {
+                return p.add(offset as _).offset_from(range.start) as _;
+            }
+
+            p = p.add(VECTOR_SIZE);
+        }
+
+        while p < range.end {
+            if *p >= b' ' && *p <= b'~' {
+                return p.offset_from(range.start) as _;
+            }
+
+            p = p.add(1);
+        }
+    }
+
+    data.len()
+}
+
+/// AVX2 version of [`crate::find_backslash_or_double_quote_non_simd`].
+///
+/// # Safety
+/// The CPU must support AVX2.
+#[cfg_attr(not(any(miri, feature = "force-scalar")), target_feature(enable = "avx2"))]
+pub unsafe fn find_backslash_or_double_quote(data: &[u8]) -> usize {
+    let range = data.as_ptr_range();
+    let mut p = range.start;
+
+    unsafe {
+        while range.end.offset_from(p) as usize > VECTOR_SIZE {
+            let v = _mm256_loadu_si256(p as _);
+
+            let backslash = _mm256_cmpeq_epi8(v, BACKSLASH);
+            let double_quote = _mm256_cmpeq_epi8(v, DOUBLE_QUOTE);
+            let either = _mm256_or_si256(backslash, double_quote);
+            let mask = _mm256_movemask_epi8(either);
+            let offset = mask.trailing_zeros();
+
+            if mask != 0 {
+                return p.add(offset as _).offset_from(range.start) as _;
+            }
+
+            p = p.add(VECTOR_SIZE);
+        }
+
+        while p < range.end {
+            if *p == b'\\' || *p == b'"' {
+                return p.offset_from(range.start) as _;
+            }
+
+            p = p.add(1);
+        }
+    }
+
+    data.len()
+}
+
+/// AVX2 version of [`crate::find_non_ascii_non_simd`].
+///
+/// # Safety
+/// The CPU must support AVX2.
+#[cfg_attr(not(any(miri, feature = "force-scalar")), target_feature(enable = "avx2"))]
+pub unsafe fn find_non_ascii(data: &[u8]) -> usize {
+    let range = data.as_ptr_range();
+    let mut p = range.start;
+
+    unsafe {
+        while range.end.offset_from(p) as usize > VECTOR_SIZE {
+            let v = _mm256_loadu_si256(p as _);
+
+            let lower_bound = _mm256_cmpgt_epi8(v, ASCII_START);
+            let upper_bound = _mm256_cmpgt_epi8(ASCII_END, v);
+            let in_bounds = _mm256_and_si256(lower_bound, upper_bound);
+            let mask = _mm256_movemask_epi8(in_bounds);
+            let offset = mask.trailing_ones();
+
+            if mask != 0xffff_ffff_u32 as i32 {
+                return p.add(offset as _).offset_from(range.start) as _;
+            }
+
+            p = p.add(VECTOR_SIZE);
+        }
+
+        while p < range.end {
+            if *p < b' ' || *p > b'~' {
+                return p.offset_from(range.start) as _;
+            }
+
+            p = p.add(1);
+        }
+    }
+
+    data.len()
+}
+
+/// AVX2 version of [`crate::rfind_ascii_non_simd`].
+///
+/// # Safety
+/// The CPU must support AVX2.
+#[cfg_attr(not(any(miri, feature = "force-scalar")), target_feature(enable = "avx2"))]
+pub unsafe fn rfind_ascii(data: &[u8]) -> usize {
+    let range = data.as_ptr_range();
+    let mut p = range.end;
+
+    unsafe {
+        while p.offset_from(range.start) as usize > VECTOR_SIZE {
+            p = p.sub(VECTOR_SIZE);
+            let v = _mm256_loadu_si256(p as _);
+
+            let lower_bound = _mm256_cmpgt_epi8(v, ASCII_START);
+            let upper_bound = _mm256_cmpgt_epi8(ASCII_END, v);
+            let in_bounds = _mm256_and_si256(lower_bound, upper_bound);
+            let mask = _mm256_movemask_epi8(in_bounds);
+            let offset = VECTOR_SIZE as u32 - mask.leading_zeros();
+
+            if mask != 0 {
+                return p.add(offset as _).offset_from(range.start) as _;
+            }
+        }
+
+        while p > range.start {
+            if *p.sub(1) >= b' ' && *p.sub(1) <= b'~' {
+                return p.offset_from(range.start) as _;
+            }
+
+            p = p.sub(1);
+        }
+    }
+
+    0
+}
+
+/// AVX2 version of [`crate::rfind_non_ascii_non_simd`].
+///
+/// # Safety
+/// The CPU must support AVX2.
+#[cfg_attr(not(any(miri, feature = "force-scalar")), target_feature(enable = "avx2"))]
+pub unsafe fn rfind_non_ascii(data: &[u8]) -> usize {
+    let range = data.as_ptr_range();
+    let mut p = range.end;
+
+    unsafe {
+        while p.offset_from(range.start) as usize > VECTOR_SIZE {
+            p = p.sub(VECTOR_SIZE);
+            let v = _mm256_loadu_si256(p as _);
+
+            let lower_bound = _mm256_cmpgt_epi8(v, ASCII_START);
+            let upper_bound = _mm256_cmpgt_epi8(ASCII_END, v);
+            let in_bounds = _mm256_and_si256(lower_bound, upper_bound);
+            let mask = _mm256_movemask_epi8(in_bounds);
+            let offset = VECTOR_SIZE as u32 - mask.leading_ones();
+
+            if mask != 0xffff_ffff_u32 as i32 {
+                return p.add(offset as _).offset_from(range.start) as _;
+            }
+        }
+
+        while p > range.start {
+            if *p.sub(1) < b' ' || *p.sub(1) > b'~' {
+                return p.offset_from(range.start) as _;
+            }
+
+            p = p.sub(1);
+        }
+    }
+
+    0
+}
+
+/// Returns the movemask of the bytes of `block` which belong to `class`.
+///
+/// # Safety
+/// The CPU must support AVX2.
+#[cfg_attr(not(any(miri, feature = "force-scalar")), target_feature(enable = "avx2"))]
+pub unsafe fn class_mask(class: crate::ByteClass, block: &[u8; VECTOR_SIZE]) -> u32 {
+    use crate::ByteClass::*;
+
+    unsafe {
+        let v = _mm256_loadu_si256(block.as_ptr() as _);
+
+        let matches = match class {
+            Printable | NonPrintable => {
+                let lower_bound = _mm256_cmpgt_epi8(v, ASCII_START);
+                let upper_bound = _mm256_cmpgt_epi8(ASCII_END, v);
+                _mm256_and_si256(lower_bound, upper_bound)
+            }
+            BackslashOrDoubleQuote => {
+                let backslash = _mm256_cmpeq_epi8(v, BACKSLASH);
+                let double_quote = _mm256_cmpeq_epi8(v, DOUBLE_QUOTE);
+                _mm256_or_si256(backslash, double_quote)
+            }
+        };
+        let mask = _mm256_movemask_epi8(matches) as u32;
+
+        if class == NonPrintable { !mask } else { mask }
+    }
+}
+
+/// Number of vectors which can be accumulated into per-lane byte counters
+/// before they can overflow.
+const MAX_BYTE_COUNTER: usize = 255;
+
+/// Returns the sum of the 32 unsigned bytes of `counters`.
+///
+/// # Safety
+/// The CPU must support AVX2.
+#[inline]
+#[cfg_attr(not(any(miri, feature = "force-scalar")), target_feature(enable = "avx2"))]
+unsafe fn sum_byte_counters(counters: __m256i) -> usize {
+    // psadbw against zero sums each group of 8 bytes into a u64.
+    let sums = _mm256_sad_epu8(counters, _mm256_setzero_si256());
+    let sums: [u64; 4] = unsafe { transmute(sums) };
+    return (sums[0] + sums[1] + sums[2] + sums[3]) as usize;
+}
+
+/// AVX2 version of [`crate::count_printable_non_simd`].
+///
+/// # Safety
+/// The CPU must support AVX2.
+#[cfg_attr(not(any(miri, feature = "force-scalar")), target_feature(enable = "avx2"))]
+pub unsafe fn count_printable(data: &[u8]) -> usize {
+    let range = data.as_ptr_range();
+    let mut p = range.start;
+    let mut count = 0;
+
+    unsafe {
+        while range.end.offset_from(p) as usize >= VECTOR_SIZE {
+            // Matches are 0xff, i.e. -1, so subtracting them counts them.
+            let mut counters = _mm256_setzero_si256();
+            let mut n = 0;
+            while n < MAX_BYTE_COUNTER && range.end.offset_from(p) as usize >= VECTOR_SIZE {
+                let v = _mm256_loadu_si256(p as _);
+
+                let lower_bound = _mm256_cmpgt_epi8(v, ASCII_START);
+                let upper_bound = _mm256_cmpgt_epi8(ASCII_END, v);
+                let in_bounds = _mm256_and_si256(lower_bound, upper_bound);
+                counters = _mm256_sub_epi8(counters, in_bounds);
+
+                p = p.add(VECTOR_SIZE);
+                n += 1;
+            }
+            count += sum_byte_counters(counters);
+        }
+
+        while p < range.end {
+            count += (*p >= b' ' && *p <= b'~') as usize;
+            p = p.add(1);
+        }
+    }
+
+    count
+}
+
+/// AVX2 version of [`crate::count_backslash_or_double_quote_non_simd`].
+///
+/// # Safety
+/// The CPU must support AVX2.
+#[cfg_attr(not(any(miri, feature = "force-scalar")), target_feature(enable = "avx2"))]
+pub unsafe fn count_backslash_or_double_quote(data: &[u8]) -> usize {
+    let range = data.as_ptr_range();
+    let mut p = range.start;
+    let mut count = 0;
+
+    unsafe {
+        while range.end.offset_from(p) as usize >= VECTOR_SIZE {
+            // Matches are 0xff, i.e. -1, so subtracting them counts them.
+            let mut counters = _mm256_setzero_si256();
+            let mut n = 0;
+            while n < MAX_BYTE_COUNTER && range.end.offset_from(p) as usize >= VECTOR_SIZE {
+                let v = _mm256_loadu_si256(p as _);
+
+                let backslash = _mm256_cmpeq_epi8(v, BACKSLASH);
+                let double_quote = _mm256_cmpeq_epi8(v, DOUBLE_QUOTE);
+                let either = _mm256_or_si256(backslash, double_quote);
+                counters = _mm256_sub_epi8(counters, either);
+
+                p = p.add(VECTOR_SIZE);
+                n += 1;
+            }
+            count += sum_byte_counters(counters);
+        }
+
+        while p < range.end {
+            count += (*p == b'\\' || *p == b'"') as usize;
+            p = p.add(1);
+        }
+    }
+
+    count
+}
+
+/// Number of vectors the predicate kernels combine before testing the result.
+const UNROLL: usize = 4;
+
+const SPACE: __m256i = unsafe { transmute([b' '; 32]) };
+const DEL:   __m256i = unsafe { transmute([0x7f_u8; 32]) };
+
+/// Returns a vector with all bits set in the lanes of `v` which aren't
+/// printable ASCII. Bytes above `0x7f` are negative as `i8`, so they're below
+/// `' '` too.
+///
+/// # Safety
+/// The CPU must support AVX2.
+#[inline]
+#[cfg_attr(not(any(miri, feature = "force-scalar")), target_feature(enable = "avx2"))]
+unsafe fn non_printable(v: __m256i) -> __m256i {
+    let below_space = _mm256_cmpgt_epi8(SPACE, v);
+    let del = _mm256_cmpeq_epi8(v, DEL);
+    return _mm256_or_si256(below_space, del);
+}
+
+/// AVX2 version of [`crate::is_printable_ascii_non_simd`].
+///
+/// # Safety
+/// The CPU must support AVX2.
+#[cfg_attr(not(any(miri, feature = "force-scalar")), target_feature(enable = "avx2"))]
+pub unsafe fn is_printable_ascii(data: &[u8]) -> bool {
+    let range = data.as_ptr_range();
+    let mut p = range.start;
+
+    unsafe {
+        // Only one branch per `UNROLL` vectors, the offending byte doesn't
+        // have to be found.
+        while range.end.offset_from(p) as usize >= UNROLL * VECTOR_SIZE {
+            let mut bad = _mm256_setzero_si256();
+            for i in 0..UNROLL {
+                let v = _mm256_loadu_si256(p.add(i * VECTOR_SIZE) as _);
+                bad = _mm256_or_si256(bad, non_printable(v));
+            }
+            if _mm256_movemask_epi8(bad) != 0 {
+                return false;
+            }
+
+            p = p.add(UNROLL * VECTOR_SIZE);
+        }
+
+        while range.end.offset_from(p) as usize >= VECTOR_SIZE {
+            let v = _mm256_loadu_si256(p as _);
+            if _mm256_movemask_epi8(non_printable(v)) != 0 {
+                return false;
+            }
+
+            p = p.add(VECTOR_SIZE);
+        }
+
+        while p < range.end {
+            if *p < b' ' || *p > b'~' {
+                return false;
+            }
+
+            p = p.add(1);
+        }
+    }
+
+    true
+}
+
+/// AVX2 version of [`crate::needs_escaping_non_simd`].
+///
+/// # Safety
+/// The CPU must support AVX2.
+#[cfg_attr(not(any(miri, feature = "force-scalar")), target_feature(enable = "avx2"))]
+pub unsafe fn needs_escaping(data: &[u8]) -> bool {
+    let range = data.as_ptr_range();
+    let mut p = range.start;
+
+    unsafe {
+        while range.end.offset_from(p) as usize >= UNROLL * VECTOR_SIZE {
+            let mut bad = _mm256_setzero_si256();
+            for i in 0..UNROLL {
+                let v = _mm256_loadu_si256(p.add(i * VECTOR_SIZE) as _);
+                let backslash = _mm256_cmpeq_epi8(v, BACKSLASH);
+                let double_quote = _mm256_cmpeq_epi8(v, DOUBLE_QUOTE);
+                bad = _mm256_or_si256(bad, non_printable(v));
+                bad = _mm256_or_si256(bad, _mm256_or_si256(backslash, double_quote));
+            }
+            if _mm256_movemask_epi8(bad) != 0 {
+                return true;
+            }
+
+            p = p.add(UNROLL * VECTOR_SIZE);
+        }
+
+        while range.end.offset_from(p) as usize >= VECTOR_SIZE {
+            let v = _mm256_loadu_si256(p as _);
+            let backslash = _mm256_cmpeq_epi8(v, BACKSLASH);
+            let double_quote = _mm256_cmpeq_epi8(v, DOUBLE_QUOTE);
+            let bad = _mm256_or_si256(non_printable(v), _mm256_or_si256(backslash, double_quote));
+            if _mm256_movemask_epi8(bad) != 0 {
+                return true;
+            }
+
+            p = p.add(VECTOR_SIZE);
+        }
+
+        while p < range.end {
+            if *p < b' ' || *p > b'~' || *p == b'\\' || *p == b'"' {
+                return true;
+            }
+
+            p = p.add(1);
+        }
+    }
+
+    false
+}
@@ -1,123 +1,134 @@
+//! The `find_*_simd` functions and the encoders built on top of them are safe
+//! to call on any x86_64 CPU: the AVX2 kernels in [`kernels`] are only used
+//! after checking that the CPU supports AVX2, otherwise the scalar
+//! `find_*_non_simd` versions are used.
+//!
+//! The crate is `no_std`: the scanners only need `core` and the encoders only
+//! need `alloc`. The default `std` feature enables runtime AVX2 detection and
+//! the thread-local helpers in [`rng`]. Without it AVX2 is only used if it's
+//! enabled at compile time, e.g. with `-C target-feature=+avx2`.
+#![no_std]
 #![allow(non_camel_case_types)]
-use core::arch::x86_64::*;
-use std::mem::*;
 
-pub const ASCII_END:    __m256i = unsafe { transmute([b'~' + 1; 32]) };
-pub const ASCII_START:  __m256i = unsafe { transmute([b' ' - 1; 32]) };
-pub const BACKSLASH:    __m256i = unsafe { transmute([b'\\'; 32]) };
-pub const DOUBLE_QUOTE: __m256i = unsafe { transmute([b'"'; 32]) };
-
-const VECTOR_SIZE: usize = std::mem::size_of::<__m256i>();
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+pub mod content;
+pub mod count;
+pub mod escape;
+pub mod ffi;
+pub mod histogram;
+mod kernels;
+pub mod matches;
+#[cfg(any(miri, feature = "force-scalar"))]
+mod portable;
+pub mod offsets;
+pub mod predicates;
+pub mod rng;
+pub mod runs;
+pub mod strings;
+
+use kernels::VECTOR_SIZE;
+pub use kernels::{ASCII_END, ASCII_START, BACKSLASH, DOUBLE_QUOTE};
+pub use content::{can_print_verbatim, classify_content, classify_content_with, ContentKind};
+pub use count::{count_backslash_or_double_quote, count_non_printable, count_printable};
+pub use count::{count_backslash_or_double_quote_non_simd, count_non_printable_non_simd, count_printable_non_simd};
+pub use escape::{escape_cow, escaped_len, escaped_segments, EscapedSegments, Segment};
+#[cfg(feature = "std")]
+pub use escape::write_escaped;
+pub use histogram::{byte_histogram, byte_histogram_non_simd, printable_ratio};
+#[cfg(feature = "std")]
+pub use histogram::shannon_entropy;
+pub use matches::{match_indices, ByteClass, MatchIndices};
+pub use matches::{position_ascii, position_backslash_or_double_quote, position_non_ascii};
+pub use matches::{rposition_ascii, rposition_non_ascii};
+pub use offsets::{to_ascii_or_hex_with_offsets, OffsetMap};
+pub use predicates::{as_unescaped_str, is_printable_ascii, needs_escaping};
+pub use predicates::{is_printable_ascii_non_simd, needs_escaping_non_simd};
+pub use runs::{ascii_runs, AsciiRuns, RunKind};
+
+use alloc::string::String;
+
+/// Returns `true` if the AVX2 kernels can be used.
+///
+/// The result of `is_x86_feature_detected!` is cached by std, so this is
+/// cheap enough to call on every invocation of a `find_*_simd` function.
+/// Without std there's no runtime detection, so only the target features
+/// enabled at compile time count.
+#[inline(always)]
+fn has_avx2() -> bool {
+    // The portable emulation of the intrinsics runs anywhere.
+    if cfg!(any(miri, feature = "force-scalar")) {
+        return true;
+    }
+    #[cfg(feature = "std")]
+    return std::is_x86_feature_detected!("avx2");
+    #[cfg(not(feature = "std"))]
+    return cfg!(target_feature = "avx2");
+}
 
 #[no_mangle]
 // #[inline(never)]
 pub fn find_ascii_simd(data: &[u8]) -> usize {
-    let range = data.as_ptr_range();
-    let mut p = range.start;
-
-    unsafe {
-        while p.add(VECTOR_SIZE) < range.end {
-            let v = _mm256_loadu_si256(p as _);
-
-            let lower_bound = _mm256_cmpgt_epi8(v, ASCII_START);
-            let upper_bound = _mm256_cmpgt_epi8(ASCII_END, v);
-            let in_bounds = _mm256_and_si256(lower_bound, upper_bound);
-            let mask = _mm256_movemask_epi8(in_bounds);
-            let offset = mask.trailing_zeros();
-
-            if mask != 0 {
-                return p.add(offset as _).offset_from(range.start) as _;
-            }
-
-            p = p.add(VECTOR_SIZE);
-        }
-
-        while p < range.end {
-            if *p >= b' ' && *p <= b'~' {
-                return p.offset_from(range.start) as _;
-            }
-
-            p = p.add(1);
-        }
+    if has_avx2() {
+        // SAFETY: AVX2 support was checked right above.
+        unsafe { kernels::find_ascii(data) }
+    } else {
+        find_ascii_non_simd(data)
     }
-
-    data.len()
 }
 
 #[no_mangle]
 #[inline(never)]
 pub fn find_backslash_or_double_quote_simd(data: &[u8]) -> usize {
-    let range = data.as_ptr_range();
-    let mut p = range.start;
-
-    unsafe {
-        while p.add(VECTOR_SIZE) < range.end {
-            let v = _mm256_loadu_si256(p as _);
-
-            let backslash = _mm256_cmpeq_epi8(v, BACKSLASH);
-            let double_quote = _mm256_cmpeq_epi8(v, DOUBLE_QUOTE);
-            let either = _mm256_or_si256(backslash, double_quote);
-            let mask = _mm256_movemask_epi8(either);
-            let offset = mask.trailing_zeros();
-
-            if mask != 0 {
-                return p.add(offset as _).offset_from(range.start) as _;
-            }
-
-            p = p.add(VECTOR_SIZE);
-        }
-
-        while p < range.end {
-            if *p == b'\\' || *p == b'"' {
-                return p.offset_from(range.start) as _;
-            }
-
-            p = p.add(1);
-        }
+    if has_avx2() {
+        // SAFETY: AVX2 support was checked right above.
+        unsafe { kernels::find_backslash_or_double_quote(data) }
+    } else {
+        find_backslash_or_double_quote_non_simd(data)
     }
-
-    data.len()
 }
 
 #[no_mangle]
 // #[inline(never)]
 pub fn find_non_ascii_simd(data: &[u8]) -> usize {
-    let range = data.as_ptr_range();
-    let mut p = range.start;
-
-    unsafe {
-        while p.add(VECTOR_SIZE) < range.end {
-            let v = _mm256_loadu_si256(p as _);
-
-            let lower_bound = _mm256_cmpgt_epi8(v, ASCII_START);
-            let upper_bound = _mm256_cmpgt_epi8(ASCII_END, v);
-            let in_bounds = _mm256_and_si256(lower_bound, upper_bound);
-            let mask = _mm256_movemask_epi8(in_bounds);
-            let offset = mask.trailing_ones();
-
-            if mask != 0xffff_ffff_u32 as i32 {
-                return p.add(offset as _).offset_from(range.start) as _;
-            }
-
-            p = p.add(VECTOR_SIZE);
-        }
-
-        while p < range.end {
-            if *p < b' ' || *p > b'~' {
-                return p.offset_from(range.start) as _;
-            }
+    if has_avx2() {
+        // SAFETY: AVX2 support was checked right above.
+        unsafe { kernels::find_non_ascii(data) }
+    } else {
+        find_non_ascii_non_simd(data)
+    }
+}
 
-            p = p.add(1);
-        }
+/// Returns the index one past the last printable ASCII byte in `data`, or 0 if
+/// there is none, so that `data.split_at(i)` puts all of the trailing
+/// non-printable bytes into the second half.
+#[no_mangle]
+pub fn rfind_ascii_simd(data: &[u8]) -> usize {
+    if has_avx2() {
+        // SAFETY: AVX2 support was checked right above.
+        unsafe { kernels::rfind_ascii(data) }
+    } else {
+        rfind_ascii_non_simd(data)
     }
+}
 
-    data.len()
+/// Returns the index one past the last byte in `data` which isn't printable
+/// ASCII, or 0 if there is none.
+#[no_mangle]
+pub fn rfind_non_ascii_simd(data: &[u8]) -> usize {
+    if has_avx2() {
+        // SAFETY: AVX2 support was checked right above.
+        unsafe { kernels::rfind_non_ascii(data) }
+    } else {
+        rfind_non_ascii_non_simd(data)
+    }
 }
 
 #[no_mangle]
 #[inline(never)]
 pub fn to_ascii_or_hex_simd_v1(data: &[u8], out: &mut String) {
-    static mut BUFFER: [u8; 4] = *b"\\x00";
     let mut tail = data;
     while !tail.is_empty() {
         let (ascii_piece, non_ascii_piece);
@@ -162,37 +173,23 @@ pub fn to_ascii_or_hex_simd_v1(data: &[u8], out: &mut String) {
 #[no_mangle]
 #[inline(never)]
 pub fn to_ascii_or_hex_simd_v2(data: &[u8], out: &mut String) {
-    let dummy = String::new();
-    let mut buffer = std::mem::replace(out, dummy).into_bytes();
-    buffer.reserve_exact(buffer.capacity() - data.len() * 4);
-
-    let mut tail = data;
-    while !tail.is_empty() {
-        let (ascii_piece, non_ascii_piece);
-
-        let i = find_ascii_simd(tail);
-        (non_ascii_piece, tail) = tail.split_at(i);
-        for c in non_ascii_piece {
-            buffer.extend_from_slice(b"\\x");
-            buffer.push(HEX_DIGIT[(c >> 4) as usize]);
-            buffer.push(HEX_DIGIT[(c & 0xf) as usize]);
-            // out.push_str("\\x");
-            // out.push((c >> 4) as _);
-            // out.push((c & 0xf) as _);
-        }
+    if let Some(s) = as_unescaped_str(data) {
+        out.push_str(s);
+        return;
+    }
 
-        if tail.is_empty() { break; }
+    let dummy = String::new();
+    let mut buffer = core::mem::replace(out, dummy).into_bytes();
+    buffer.reserve(data.len() * 4);
 
-        let i = find_non_ascii_simd(tail);
-        (ascii_piece, tail) = tail.split_at(i);
-        for &c in ascii_piece {
-            if matches!(c, b'\\' | b'"') { buffer.push(b'\\'); }
-            buffer.push(c);
-        }
-    }
+    escape::escape_with(data, &mut buffer);
 
+    // The buffer started out as a valid `String` and only printable ASCII
+    // bytes were appended to it since.
+    debug_assert!(core::str::from_utf8(&buffer).is_ok(), "{buffer:?}");
+    // SAFETY: see above.
     let s = unsafe { String::from_utf8_unchecked(buffer) };
-    std::mem::replace(out, s);
+    *out = s;
 }
 
 const HEX_DIGIT: [u8; 16] = *b"0123456789abcdef";
@@ -215,18 +212,15 @@ pub fn to_ascii_or_hex(data: &[u8], out: &mut String) {
 #[no_mangle]
 #[inline(never)]
 pub fn find_ascii_non_simd(data: &[u8]) -> usize {
-    let range = data.as_ptr_range();
     let mut i = 0;
 
-    unsafe {
-        while i < data.len() {
-            let c = data[i];
-            if c >= b' ' && c <= b'~' {
-                return i;
-            }
-
-            i += 1;
+    while i < data.len() {
+        let c = data[i];
+        if c >= b' ' && c <= b'~' {
+            return i;
         }
+
+        i += 1;
     }
 
     return i;
@@ -235,18 +229,66 @@ pub fn find_ascii_non_simd(data: &[u8]) -> usize {
 #[no_mangle]
 #[inline(never)]
 pub fn find_non_ascii_non_simd(data: &[u8]) -> usize {
-    let range = data.as_ptr_range();
     let mut i = 0;
 
-    unsafe {
-        while i < data.len() {
-            let c = data[i];
-            if c < b' ' || c > b'~' {
-                return i;
-            }
+    while i < data.len() {
+        let c = data[i];
+        if c < b' ' || c > b'~' {
+            return i;
+        }
 
-            i += 1;
+        i += 1;
+    }
+
+    return i;
+}
+
+#[no_mangle]
+#[inline(never)]
+pub fn find_backslash_or_double_quote_non_simd(data: &[u8]) -> usize {
+    let mut i = 0;
+
+    while i < data.len() {
+        let c = data[i];
+        if c == b'\\' || c == b'"' {
+            return i;
+        }
+
+        i += 1;
+    }
+
+    return i;
+}
+
+#[no_mangle]
+#[inline(never)]
+pub fn rfind_ascii_non_simd(data: &[u8]) -> usize {
+    let mut i = data.len();
+
+    while i > 0 {
+        let c = data[i - 1];
+        if c >= b' ' && c <= b'~' {
+            return i;
         }
+
+        i -= 1;
+    }
+
+    return i;
+}
+
+#[no_mangle]
+#[inline(never)]
+pub fn rfind_non_ascii_non_simd(data: &[u8]) -> usize {
+    let mut i = data.len();
+
+    while i > 0 {
+        let c = data[i - 1];
+        if c < b' ' || c > b'~' {
+            return i;
+        }
+
+        i -= 1;
     }
 
     return i;
@@ -259,7 +301,7 @@ pub fn find_ascii_non_simd_unrolled(data: &[u8]) -> usize {
     let mut p = range.start;
 
     unsafe {
-        while p.add(VECTOR_SIZE) < range.end {
+        while range.end.offset_from(p) as usize > VECTOR_SIZE {
             if *p         >= b' ' && *p         <= b'~' { return p        .offset_from(range.start) as _; }
             if *p.add( 1) >= b' ' && *p.add( 1) <= b'~' { return p.add( 1).offset_from(range.start) as _; }
             if *p.add( 2) >= b' ' && *p.add( 2) <= b'~' { return p.add( 2).offset_from(range.start) as _; }
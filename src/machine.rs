@@ -0,0 +1,135 @@
+////////////////////////////////////////////////////////////////////////////////
+// Machine
+////////////////////////////////////////////////////////////////////////////////
+
+use std::fmt::Write as _;
+
+/// Description of the machine and the build which produced a set of benchmark
+/// results. Printed at the top of the benchmark output, so that results from
+/// different machines can be compared.
+pub struct Machine {
+    pub cpu_model: String,
+    pub logical_cpus: usize,
+    pub kernel: String,
+    pub governor: String,
+    pub caches: Vec<Cache>,
+    pub runtime_features: Vec<&'static str>,
+    pub target_features: Vec<&'static str>,
+}
+
+pub struct Cache {
+    pub level: u32,
+    /// `Data`, `Instruction` or `Unified`.
+    pub kind: String,
+    /// Size as reported by sysfs, e.g. `32K`.
+    pub size: String,
+}
+
+macro_rules! detect_features {
+    ($($feature:tt),* $(,)?) => {
+        /// Features detected at runtime with `is_x86_feature_detected!`.
+        fn runtime_features() -> Vec<&'static str> {
+            let mut res = Vec::new();
+            $( if is_x86_feature_detected!($feature) { res.push($feature); } )*
+            return res;
+        }
+
+        /// Features enabled at compile time, e.g. with `-C target-cpu=native`.
+        fn target_features() -> Vec<&'static str> {
+            let mut res = Vec::new();
+            $( if cfg!(target_feature = $feature) { res.push($feature); } )*
+            return res;
+        }
+    };
+}
+
+detect_features! {
+    "sse2", "sse3", "ssse3", "sse4.1", "sse4.2", "popcnt", "lzcnt", "bmi1", "bmi2",
+    "fma", "avx", "avx2", "avx512f", "avx512bw", "avx512vl",
+}
+
+impl Machine {
+    pub fn detect() -> Self {
+        Self {
+            cpu_model: cpu_model(),
+            logical_cpus: std::thread::available_parallelism().map_or(0, |n| n.get()),
+            kernel: read_trimmed("/proc/sys/kernel/osrelease").unwrap_or_else(|| "unknown".into()),
+            governor: read_trimmed("/sys/devices/system/cpu/cpu0/cpufreq/scaling_governor")
+                .unwrap_or_else(|| "unknown".into()),
+            caches: caches(),
+            runtime_features: runtime_features(),
+            target_features: target_features(),
+        }
+    }
+}
+
+impl std::fmt::Display for Machine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "============================================================")?;
+        writeln!(f, "machine")?;
+        writeln!(f, "cpu: {}", self.cpu_model)?;
+        writeln!(f, "logical cpus: {}", self.logical_cpus)?;
+        writeln!(f, "kernel: {}", self.kernel)?;
+        writeln!(f, "governor: {}", self.governor)?;
+
+        let mut caches = String::new();
+        for cache in &self.caches {
+            if !caches.is_empty() { caches.push_str(", "); }
+            let suffix = match cache.kind.as_str() {
+                "Data" => "d",
+                "Instruction" => "i",
+                _ => "",
+            };
+            write!(caches, "L{}{suffix} {}", cache.level, cache.size)?;
+        }
+        if caches.is_empty() { caches.push_str("unknown"); }
+        writeln!(f, "caches: {caches}")?;
+
+        writeln!(f, "runtime features: {}", self.runtime_features.join(" "))?;
+        writeln!(f, "target features: {}", self.target_features.join(" "))?;
+        Ok(())
+    }
+}
+
+/// Returns the processor brand string reported by `cpuid`.
+fn cpu_model() -> String {
+    use core::arch::x86_64::__cpuid;
+
+    let max_extended_leaf = __cpuid(0x8000_0000).eax;
+    if max_extended_leaf < 0x8000_0004 {
+        return "unknown".into();
+    }
+
+    let mut brand = Vec::with_capacity(48);
+    for leaf in 0x8000_0002..=0x8000_0004 {
+        let r = __cpuid(leaf);
+        for register in [r.eax, r.ebx, r.ecx, r.edx] {
+            brand.extend_from_slice(&register.to_le_bytes());
+        }
+    }
+
+    let brand = String::from_utf8_lossy(&brand);
+    return brand.trim_end_matches('\0').trim().into();
+}
+
+/// Reads cache descriptions of the first cpu from
+/// `/sys/devices/system/cpu/cpu0/cache/index*`.
+fn caches() -> Vec<Cache> {
+    let mut res = Vec::new();
+    for index in 0.. {
+        let dir = format!("/sys/devices/system/cpu/cpu0/cache/index{index}");
+        let Some(level) = read_trimmed(&format!("{dir}/level")) else { break };
+        let Ok(level) = level.parse() else { continue };
+        res.push(Cache {
+            level,
+            kind: read_trimmed(&format!("{dir}/type")).unwrap_or_default(),
+            size: read_trimmed(&format!("{dir}/size")).unwrap_or_default(),
+        });
+    }
+    return res;
+}
+
+fn read_trimmed(path: &str) -> Option<String> {
+    let s = std::fs::read_to_string(path).ok()?;
+    Some(s.trim().into())
+}
@@ -1,26 +1,81 @@
 // RUSTFLAGS='-C target-cpu=native' cargo run --release
+// cargo run --release -- strings [-n MIN_LEN] [-t d|o|x] [-0] [FILE]...
 #![allow(non_camel_case_types)]
 #![allow(non_snake_case)]
 
-mod rng;
+mod machine;
+mod perf_event;
+mod strings_cmd;
+mod timing;
 
-use core::arch::x86_64::*;
 use simd_rs::*;
 
-unsafe fn perf_test(N: usize, L: usize) {
+struct Harness {
+    timer: timing::Timer,
+    counters: perf_event::Counters,
+}
+
+impl Harness {
+    fn new() -> Self {
+        let timer = timing::Timer::new(timing::Backend::from_env());
+        println!("timer: {:?}, tsc: {:.3} GHz", timer.backend(), timer.tsc_hz() / 1e9);
+        let counters = perf_event::Counters::open();
+        if let Some(reason) = counters.unavailable_reason() {
+            println!("hardware counters: unavailable ({reason})");
+        }
+        Self { timer, counters }
+    }
+
+    /// Times a single run of `f` which processes `n_bytes` bytes of input.
+    fn run(&mut self, name: &str, n_bytes: usize, f: impl FnOnce()) {
+        self.counters.start();
+        let m = self.timer.measure(f);
+        let sample = self.counters.stop();
+        print!("{name}: {:?} ({:.3} cycles/byte)", m.elapsed, m.cycles_per_byte(n_bytes));
+        match sample {
+            Some(sample) => println!(" [{sample}]"),
+            None => println!(" [counters: unavailable]"),
+        }
+    }
+}
+
+/// Prints the byte classes actually present in the generated input.
+fn describe_input(data: &[u8]) {
+    let histogram = byte_histogram(data);
+    let total = data.len().max(1) as f64;
+    let special = (histogram[b'\\' as usize] + histogram[b'"' as usize]) as f64 / total;
+    let control = histogram[..b' ' as usize].iter().sum::<u64>() as f64 / total;
+    let high = histogram[0x80..].iter().sum::<u64>() as f64 / total;
+    println!("input: printable {:.1}%, backslash or double quote {:.1}%, control {:.1}%, high {:.1}%, entropy {:.3} bits/byte",
+        printable_ratio(&histogram) * 100.0, special * 100.0, control * 100.0, high * 100.0, shannon_entropy(&histogram),
+    );
+}
+
+/// A named distribution of the benchmark input bytes.
+struct Mix {
+    name: &'static str,
+    dist: rng::ByteDistribution,
+}
+
+impl Mix {
+    fn new(name: &'static str, ranges: &[(u32, std::ops::RangeInclusive<u8>)]) -> Self {
+        Self { name, dist: rng::ByteDistribution::new(ranges) }
+    }
+}
+
+unsafe fn perf_test(h: &mut Harness, N: usize, L: usize, mix: &Mix) {
     println!("============================================================");
-    println!("perf_test({N}, {L})");
+    println!("perf_test({N}, {L}, {})", mix.name);
     let n_bytes = N * L;
-    let mut data: Vec<u8> = Vec::with_capacity(n_bytes);
-    for i in 0..n_bytes / 8 {
-        let n = rng::random_u64();
-        data.extend_from_slice(as_slice(&n));
-    }
+    let mut data = vec![0_u8; n_bytes];
+    rng::fill_bytes_from(&mix.dist, &mut data);
+    describe_input(&data);
     let get_slice = |i| &data[i * L..(i + 1) * L];
 
     let mut results_ascii_or_hex_simd_v1 = Vec::with_capacity(N);
     let mut results_ascii_or_hex_simd_v2 = Vec::with_capacity(N);
     let mut results_ascii_or_hex_non_simd = Vec::with_capacity(N);
+    let mut out_write_escaped = Vec::with_capacity(4 * L);
     for i in 0..N {
         let slice = get_slice(i);
         results_ascii_or_hex_simd_v1.push(String::with_capacity(4 * slice.len()));
@@ -30,30 +85,55 @@ unsafe fn perf_test(N: usize, L: usize) {
 
     for _ in 0..4 {
 
-        let t0 = std::time::Instant::now();
-        for i in 0..N {
-            let slice = get_slice(i);
-            to_ascii_or_hex(slice, &mut results_ascii_or_hex_non_simd[i]);
-        }
-        println!("non simd: {:?}", t0.elapsed());
+        h.run("non simd", n_bytes, || {
+            for i in 0..N {
+                let slice = get_slice(i);
+                to_ascii_or_hex(slice, &mut results_ascii_or_hex_non_simd[i]);
+            }
+        });
 
-        let t0 = std::time::Instant::now();
-        for i in 0..N {
-            let slice = get_slice(i);
-            to_ascii_or_hex_simd_v1(slice, &mut results_ascii_or_hex_simd_v1[i]);
-        }
-        println!("simd_v1: {:?}", t0.elapsed());
+        h.run("simd_v1", n_bytes, || {
+            for i in 0..N {
+                let slice = get_slice(i);
+                to_ascii_or_hex_simd_v1(slice, &mut results_ascii_or_hex_simd_v1[i]);
+            }
+        });
 
-        let t0 = std::time::Instant::now();
-        for i in 0..N {
-            let slice = get_slice(i);
-            to_ascii_or_hex_simd_v2(slice, &mut results_ascii_or_hex_simd_v2[i]);
-        }
-        println!("simd_v2: {:?}", t0.elapsed());
+        h.run("simd_v2", n_bytes, || {
+            for i in 0..N {
+                let slice = get_slice(i);
+                to_ascii_or_hex_simd_v2(slice, &mut results_ascii_or_hex_simd_v2[i]);
+            }
+        });
+
+        // Holding on to N more outputs of up to 4 * L bytes would take
+        // gigabytes for the largest inputs, so these two only keep the
+        // current one and are checked separately below.
+        h.run("escape_cow", n_bytes, || {
+            for i in 0..N {
+                let slice = get_slice(i);
+                std::hint::black_box(escape_cow(slice));
+            }
+        });
+
+        // A `Vec` copies the verbatim segments anyway, so this only shows the
+        // overhead of the segments compared to the encoders above.
+        h.run("write_escaped", n_bytes, || {
+            for i in 0..N {
+                let slice = get_slice(i);
+                out_write_escaped.clear();
+                write_escaped(&mut out_write_escaped, slice).unwrap();
+                std::hint::black_box(&out_write_escaped);
+            }
+        });
 
         for i in 0..N {
             assert_eq!(results_ascii_or_hex_simd_v1[i], results_ascii_or_hex_non_simd[i], "{:?}", get_slice(i));
             assert_eq!(results_ascii_or_hex_simd_v2[i], results_ascii_or_hex_non_simd[i], "{:?}", get_slice(i));
+            assert_eq!(escape_cow(get_slice(i)), results_ascii_or_hex_non_simd[i], "{:?}", get_slice(i));
+            out_write_escaped.clear();
+            write_escaped(&mut out_write_escaped, get_slice(i)).unwrap();
+            assert_eq!(out_write_escaped, results_ascii_or_hex_non_simd[i].as_bytes(), "{:?}", get_slice(i));
 
             reset_string(&mut results_ascii_or_hex_simd_v1[i]);
             reset_string(&mut results_ascii_or_hex_simd_v2[i]);
@@ -70,15 +150,13 @@ fn reset_string(s: &mut String) {
     }
 }
 
-unsafe fn perf_test_find(N: usize, L: usize) {
+unsafe fn perf_test_find(h: &mut Harness, N: usize, L: usize, mix: &Mix) {
     println!("============================================================");
-    println!("perf_test({N}, {L})");
+    println!("perf_test_find({N}, {L}, {})", mix.name);
     let n_bytes = N * L;
-    let mut data: Vec<u8> = Vec::with_capacity(n_bytes);
-    for i in 0..n_bytes / 8 {
-        let n = rng::random_u64();
-        data.extend_from_slice(as_slice(&n));
-    }
+    let mut data = vec![0_u8; n_bytes];
+    rng::fill_bytes_from(&mix.dist, &mut data);
+    describe_input(&data);
     let get_slice = |i| &data[i * L..(i + 1) * L];
     let mut simd_results = vec![0_usize; N];
     let mut non_simd_results = vec![0_usize; N];
@@ -86,62 +164,298 @@ unsafe fn perf_test_find(N: usize, L: usize) {
 
     for _ in 0..4 {
 
-        let t0 = std::time::Instant::now();
+        h.run("simd", n_bytes, || {
+            for i in 0..N {
+                let slice = get_slice(i);
+                let index = find_ascii_simd(slice);
+                simd_results[i] = index;
+            }
+        });
+
+        h.run("non simd", n_bytes, || {
+            for i in 0..N {
+                let slice = get_slice(i);
+                let index = find_ascii_non_simd(slice);
+                non_simd_results[i] = index;
+            }
+        });
+
+        h.run("non simd unrolled", n_bytes, || {
+            for i in 0..N {
+                let slice = get_slice(i);
+                let index = find_ascii_non_simd_unrolled(slice);
+                non_simd_unrolled_results[i] = index;
+            }
+        });
+
         for i in 0..N {
-            let slice = get_slice(i);
-            let index = find_ascii_simd(slice);
-            simd_results[i] = index;
+            assert_eq!(simd_results[i], non_simd_results[i], "{:?}", get_slice(i));
+            assert_eq!(non_simd_results[i], non_simd_unrolled_results[i], "{:?}", get_slice(i));
         }
-        println!("simd: {:?}", t0.elapsed());
 
-        let t0 = std::time::Instant::now();
+        h.run("non-ascii simd", n_bytes, || {
+            for i in 0..N {
+                let slice = get_slice(i);
+                let index = find_non_ascii_simd(slice);
+                simd_results[i] = index;
+            }
+        });
+
+        h.run("non-ascii non simd", n_bytes, || {
+            for i in 0..N {
+                let slice = get_slice(i);
+                let index = find_non_ascii_non_simd(slice);
+                non_simd_results[i] = index;
+            }
+        });
+
         for i in 0..N {
-            let slice = get_slice(i);
-            let index = find_ascii_non_simd(slice);
-            non_simd_results[i] = index;
+            assert_eq!(simd_results[i], non_simd_results[i], "{:?}", get_slice(i));
         }
-        println!("non simd: {:?}", t0.elapsed());
 
-        let t0 = std::time::Instant::now();
+        h.run("backslash or double quote simd", n_bytes, || {
+            for i in 0..N {
+                let slice = get_slice(i);
+                let index = find_backslash_or_double_quote_simd(slice);
+                simd_results[i] = index;
+            }
+        });
+
+        h.run("backslash or double quote non simd", n_bytes, || {
+            for i in 0..N {
+                let slice = get_slice(i);
+                let index = find_backslash_or_double_quote_non_simd(slice);
+                non_simd_results[i] = index;
+            }
+        });
+
         for i in 0..N {
-            let slice = get_slice(i);
-            let index = find_ascii_non_simd_unrolled(slice);
-            non_simd_unrolled_results[i] = index;
+            assert_eq!(simd_results[i], non_simd_results[i], "{:?}", get_slice(i));
         }
-        println!("non simd unrolled: {:?}", t0.elapsed());
+
+        // Counting every match rather than just finding the first one.
+        h.run("all backslash or double quote: find loop", n_bytes, || {
+            for i in 0..N {
+                let mut tail = get_slice(i);
+                let mut count = 0;
+                loop {
+                    let index = find_backslash_or_double_quote_simd(tail);
+                    if index == tail.len() { break; }
+                    count += 1;
+                    tail = &tail[index + 1..];
+                }
+                simd_results[i] = count;
+            }
+        });
+
+        h.run("all backslash or double quote: match_indices", n_bytes, || {
+            for i in 0..N {
+                let slice = get_slice(i);
+                non_simd_results[i] = match_indices(slice, ByteClass::BackslashOrDoubleQuote).count();
+            }
+        });
 
         for i in 0..N {
             assert_eq!(simd_results[i], non_simd_results[i], "{:?}", get_slice(i));
-            assert_eq!(non_simd_results[i], non_simd_unrolled_results[i], "{:?}", get_slice(i));
         }
+    }
+}
 
-        let t0 = std::time::Instant::now();
-        for i in 0..N {
-            let slice = get_slice(i);
-            let index = find_non_ascii_simd(slice);
-            simd_results[i] = index;
+unsafe fn perf_test_count(h: &mut Harness, N: usize, L: usize, mix: &Mix) {
+    println!("============================================================");
+    println!("perf_test_count({N}, {L}, {})", mix.name);
+    let n_bytes = N * L;
+    let mut data = vec![0_u8; n_bytes];
+    rng::fill_bytes_from(&mix.dist, &mut data);
+    describe_input(&data);
+    let get_slice = |i| &data[i * L..(i + 1) * L];
+    let mut simd_results = vec![0_usize; N];
+    let mut non_simd_results = vec![0_usize; N];
+
+    type Counter = fn(&[u8]) -> usize;
+    let counters: [(&str, Counter, Counter); 3] = [
+        ("count_printable", count_printable, count_printable_non_simd),
+        ("count_non_printable", count_non_printable, count_non_printable_non_simd),
+        ("count_backslash_or_double_quote", count_backslash_or_double_quote, count_backslash_or_double_quote_non_simd),
+    ];
+
+    for _ in 0..4 {
+        for (name, simd, non_simd) in counters {
+
+            h.run(&format!("{name} simd"), n_bytes, || {
+                for i in 0..N {
+                    simd_results[i] = simd(get_slice(i));
+                }
+            });
+
+            h.run(&format!("{name} non simd"), n_bytes, || {
+                for i in 0..N {
+                    non_simd_results[i] = non_simd(get_slice(i));
+                }
+            });
+
+            for i in 0..N {
+                assert_eq!(simd_results[i], non_simd_results[i], "{name}: {:?}", get_slice(i));
+            }
         }
-        println!("non-ascii simd: {:?}", t0.elapsed());
+    }
+}
+
+unsafe fn perf_test_histogram(h: &mut Harness, N: usize, L: usize, mix: &Mix) {
+    println!("============================================================");
+    println!("perf_test_histogram({N}, {L}, {})", mix.name);
+    let n_bytes = N * L;
+    let mut data = vec![0_u8; n_bytes];
+    rng::fill_bytes_from(&mix.dist, &mut data);
+    describe_input(&data);
+    let get_slice = |i| &data[i * L..(i + 1) * L];
+    let mut results = vec![[0_u64; 256]; N];
+    let mut non_simd_results = vec![[0_u64; 256]; N];
+
+    for _ in 0..4 {
+
+        h.run("byte_histogram", n_bytes, || {
+            for i in 0..N {
+                results[i] = byte_histogram(get_slice(i));
+            }
+        });
+
+        h.run("byte_histogram non simd", n_bytes, || {
+            for i in 0..N {
+                non_simd_results[i] = byte_histogram_non_simd(get_slice(i));
+            }
+        });
 
-        let t0 = std::time::Instant::now();
         for i in 0..N {
-            let slice = get_slice(i);
-            let index = find_non_ascii_non_simd(slice);
-            non_simd_results[i] = index;
+            assert_eq!(results[i], non_simd_results[i], "{:?}", get_slice(i));
         }
-        println!("non-ascii non simd: {:?}", t0.elapsed());
+    }
+}
+
+/// Compares [`ascii_runs`] with the same loop written by hand, each pass
+/// counts the runs and the printable bytes of every slice.
+unsafe fn perf_test_runs(h: &mut Harness, N: usize, L: usize, mix: &Mix) {
+    println!("============================================================");
+    println!("perf_test_runs({N}, {L}, {})", mix.name);
+    let n_bytes = N * L;
+    let mut data = vec![0_u8; n_bytes];
+    rng::fill_bytes_from(&mix.dist, &mut data);
+    describe_input(&data);
+    let get_slice = |i| &data[i * L..(i + 1) * L];
+    let mut hand_rolled_results = vec![(0_usize, 0_usize); N];
+    let mut iterator_results = vec![(0_usize, 0_usize); N];
+    let mut rev_iterator_results = vec![(0_usize, 0_usize); N];
+
+    for _ in 0..4 {
+
+        h.run("hand-rolled", n_bytes, || {
+            for i in 0..N {
+                let (mut n_runs, mut n_printable) = (0, 0);
+                let mut tail = get_slice(i);
+                while !tail.is_empty() {
+                    let (ascii_piece, non_ascii_piece);
+
+                    let j = find_ascii_simd(tail);
+                    (non_ascii_piece, tail) = tail.split_at(j);
+                    if !non_ascii_piece.is_empty() { n_runs += 1; }
+
+                    if tail.is_empty() { break; }
+
+                    let j = find_non_ascii_simd(tail);
+                    (ascii_piece, tail) = tail.split_at(j);
+                    n_runs += 1;
+                    n_printable += ascii_piece.len();
+                }
+                hand_rolled_results[i] = (n_runs, n_printable);
+            }
+        });
+
+        h.run("ascii_runs", n_bytes, || {
+            for i in 0..N {
+                let (mut n_runs, mut n_printable) = (0, 0);
+                for (kind, run) in ascii_runs(get_slice(i)) {
+                    n_runs += 1;
+                    if kind == RunKind::Printable { n_printable += run.len(); }
+                }
+                iterator_results[i] = (n_runs, n_printable);
+            }
+        });
+
+        h.run("ascii_runs rev", n_bytes, || {
+            for i in 0..N {
+                let (mut n_runs, mut n_printable) = (0, 0);
+                for (kind, run) in ascii_runs(get_slice(i)).rev() {
+                    n_runs += 1;
+                    if kind == RunKind::Printable { n_printable += run.len(); }
+                }
+                rev_iterator_results[i] = (n_runs, n_printable);
+            }
+        });
 
         for i in 0..N {
-            assert_eq!(simd_results[i], non_simd_results[i], "{:?}", get_slice(i));
+            assert_eq!(iterator_results[i], hand_rolled_results[i], "{:?}", get_slice(i));
+            assert_eq!(rev_iterator_results[i], hand_rolled_results[i], "{:?}", get_slice(i));
         }
     }
 }
 
-#[inline(always)]
-fn as_slice<T>(v: &T) -> &[u8] {
-    unsafe { std::slice::from_raw_parts(v as *const T as *const u8, std::mem::size_of::<T>()) }
+unsafe fn perf_test_predicates(h: &mut Harness, N: usize, L: usize, mix: &Mix) {
+    println!("============================================================");
+    println!("perf_test_predicates({N}, {L}, {})", mix.name);
+    let n_bytes = N * L;
+    let mut data = vec![0_u8; n_bytes];
+    rng::fill_bytes_from(&mix.dist, &mut data);
+    describe_input(&data);
+    let get_slice = |i| &data[i * L..(i + 1) * L];
+    let mut find_results = vec![false; N];
+    let mut simd_results = vec![false; N];
+    let mut non_simd_results = vec![false; N];
+
+    for _ in 0..4 {
+
+        h.run("find_non_ascii_simd == len", n_bytes, || {
+            for i in 0..N {
+                let slice = get_slice(i);
+                find_results[i] = find_non_ascii_simd(slice) == slice.len();
+            }
+        });
+
+        h.run("is_printable_ascii", n_bytes, || {
+            for i in 0..N {
+                simd_results[i] = is_printable_ascii(get_slice(i));
+            }
+        });
+
+        h.run("is_printable_ascii non simd", n_bytes, || {
+            for i in 0..N {
+                non_simd_results[i] = is_printable_ascii_non_simd(get_slice(i));
+            }
+        });
+
+        for i in 0..N {
+            assert_eq!(simd_results[i], find_results[i], "{:?}", get_slice(i));
+            assert_eq!(simd_results[i], non_simd_results[i], "{:?}", get_slice(i));
+        }
+
+        h.run("needs_escaping", n_bytes, || {
+            for i in 0..N {
+                simd_results[i] = needs_escaping(get_slice(i));
+            }
+        });
+
+        h.run("needs_escaping non simd", n_bytes, || {
+            for i in 0..N {
+                non_simd_results[i] = needs_escaping_non_simd(get_slice(i));
+            }
+        });
+
+        for i in 0..N {
+            assert_eq!(simd_results[i], non_simd_results[i], "{:?}", get_slice(i));
+        }
+    }
 }
 
+#[allow(dead_code)]
 fn check_find_ascii(data: &[u8]) {
     println!("------------------------------------------------------------------------------------------------");
     let i = find_ascii_simd(data);
@@ -157,11 +471,63 @@ fn check_find_non_ascii(data: &[u8]) {
 }
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("strings") {
+        std::process::exit(strings_cmd::main(&args[1..]));
+    }
+
+    print!("{}", machine::Machine::detect());
+    // Every input below is generated from this seed, so a failed check can be
+    // replayed by passing it back in.
+    println!("seed: {0:#x} (replay with {1}={0:#x})", rng::seed(), rng::SEED_ENV_VAR);
     check();
-    unsafe { perf_test(4096, 4096); }
-    unsafe { perf_test(16 * 4096, 4096); }
-    unsafe { perf_test(16 * 4096, 4 * 4096); }
-    unsafe { perf_test(16 * 4096, 1024); }
+    let mut h = Harness::new();
+
+    let uniform = Mix::new("uniform", &[(1, 0x00..=0xff)]);
+    // Uniform input is mostly non-printable, these are closer to real text.
+    let mixes = [
+        Mix::new("90% printable, 10% control", &[(90, b' '..=b'~'), (10, 0x00..=0x1f)]),
+        Mix::new("80% printable, 20% double quotes", &[(80, b' '..=b'~'), (20, b'"'..=b'"')]),
+        Mix::new("99% printable, 1% high", &[(99, b' '..=b'~'), (1, 0x80..=0xff)]),
+    ];
+
+    unsafe { perf_test_find(&mut h, 4096, 4096, &uniform); }
+    unsafe { perf_test_find(&mut h, 16 * 4096, 4096, &uniform); }
+    unsafe { perf_test_find(&mut h, 16 * 4096, 4 * 4096, &uniform); }
+    unsafe { perf_test_find(&mut h, 16 * 4096, 1024, &uniform); }
+    for mix in &mixes {
+        unsafe { perf_test_find(&mut h, 4096, 4096, mix); }
+    }
+    unsafe { perf_test_count(&mut h, 4096, 4096, &uniform); }
+    for mix in &mixes {
+        unsafe { perf_test_count(&mut h, 4096, 4096, mix); }
+    }
+    unsafe { perf_test_histogram(&mut h, 4096, 4096, &uniform); }
+    // Repeated bytes are the worst case for a single table.
+    unsafe { perf_test_histogram(&mut h, 4096, 4096, &Mix::new("zeros", &[(1, 0x00..=0x00)])); }
+    for mix in &mixes {
+        unsafe { perf_test_histogram(&mut h, 4096, 4096, mix); }
+    }
+    unsafe { perf_test_runs(&mut h, 4096, 4096, &uniform); }
+    for mix in &mixes {
+        unsafe { perf_test_runs(&mut h, 4096, 4096, mix); }
+    }
+    // The predicates are only interesting when they have to look at every
+    // byte, so mostly on clean input.
+    let clean = Mix::new("printable, no escapes", &[(1, b' '..=b'!'), (1, b'#'..=b'['), (1, b']'..=b'~')]);
+    unsafe { perf_test_predicates(&mut h, 4096, 4096, &clean); }
+    unsafe { perf_test_predicates(&mut h, 4096 * 64, 64, &clean); }
+    for mix in &mixes {
+        unsafe { perf_test_predicates(&mut h, 4096, 4096, mix); }
+    }
+    unsafe { perf_test(&mut h, 4096, 4096, &uniform); }
+    unsafe { perf_test(&mut h, 16 * 4096, 4096, &uniform); }
+    unsafe { perf_test(&mut h, 16 * 4096, 4 * 4096, &uniform); }
+    unsafe { perf_test(&mut h, 16 * 4096, 1024, &uniform); }
+    for mix in &mixes {
+        unsafe { perf_test(&mut h, 4096, 4096, mix); }
+    }
+    unsafe { perf_test(&mut h, 4096, 4096, &clean); }
 }
 
 fn check() {
@@ -202,6 +568,7 @@ impl std::fmt::Display for inspect_find_ascii_result<'_> {
     }
 }
 
+#[allow(dead_code)]
 struct inspect<'a, T>(&'a T);
 impl<T> std::fmt::Display for inspect<'_, T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -0,0 +1,162 @@
+//! `Option`-returning scanners and an iterator over every match.
+
+use crate::*;
+
+/// A set of bytes which can be searched for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ByteClass {
+    /// Bytes in `b' '..=b'~'`.
+    Printable,
+    /// Every byte outside of `b' '..=b'~'`.
+    NonPrintable,
+    /// `\` and `"`, the bytes which are escaped by the encoders.
+    BackslashOrDoubleQuote,
+}
+
+impl ByteClass {
+    #[inline(always)]
+    pub fn contains(self, c: u8) -> bool {
+        match self {
+            ByteClass::Printable => c >= b' ' && c <= b'~',
+            ByteClass::NonPrintable => c < b' ' || c > b'~',
+            ByteClass::BackslashOrDoubleQuote => c == b'\\' || c == b'"',
+        }
+    }
+
+    /// Returns the index of the first byte of `data` in the class, or
+    /// `data.len()`.
+    #[inline(always)]
+    fn find(self, data: &[u8]) -> usize {
+        match self {
+            ByteClass::Printable => find_ascii_simd(data),
+            ByteClass::NonPrintable => find_non_ascii_simd(data),
+            ByteClass::BackslashOrDoubleQuote => find_backslash_or_double_quote_simd(data),
+        }
+    }
+
+    /// Returns a mask with bit `i` set if `block[i]` belongs to the class.
+    #[inline(always)]
+    fn mask(self, block: &[u8; VECTOR_SIZE]) -> u32 {
+        if has_avx2() {
+            // SAFETY: AVX2 support was checked right above.
+            return unsafe { kernels::class_mask(self, block) };
+        }
+        let mut mask = 0;
+        for i in 0..VECTOR_SIZE {
+            mask |= (self.contains(block[i]) as u32) << i;
+        }
+        return mask;
+    }
+}
+
+/// Returns the index of the first printable ASCII byte in `data`.
+#[inline]
+pub fn position_ascii(data: &[u8]) -> Option<usize> {
+    let i = find_ascii_simd(data);
+    (i < data.len()).then_some(i)
+}
+
+/// Returns the index of the first byte in `data` which isn't printable ASCII.
+#[inline]
+pub fn position_non_ascii(data: &[u8]) -> Option<usize> {
+    let i = find_non_ascii_simd(data);
+    (i < data.len()).then_some(i)
+}
+
+/// Returns the index of the first `\` or `"` in `data`.
+#[inline]
+pub fn position_backslash_or_double_quote(data: &[u8]) -> Option<usize> {
+    let i = find_backslash_or_double_quote_simd(data);
+    (i < data.len()).then_some(i)
+}
+
+/// Returns the index of the last printable ASCII byte in `data`.
+#[inline]
+pub fn rposition_ascii(data: &[u8]) -> Option<usize> {
+    rfind_ascii_simd(data).checked_sub(1)
+}
+
+/// Returns the index of the last byte in `data` which isn't printable ASCII.
+#[inline]
+pub fn rposition_non_ascii(data: &[u8]) -> Option<usize> {
+    rfind_non_ascii_simd(data).checked_sub(1)
+}
+
+/// Returns an iterator over the indices of all bytes of `data` in `class`, in
+/// increasing order.
+///
+/// Stretches without matches are skipped with the regular scanner. From each
+/// match on a 32-byte block is loaded and compared once, then the matches in
+/// it are read off the resulting bit mask, so dense matches cost a couple of
+/// instructions each instead of a new scan.
+///
+/// ```
+/// # use simd_rs::{match_indices, ByteClass};
+/// let data = br#"say "hi" \ bye"#;
+/// let indices: Vec<_> = match_indices(data, ByteClass::BackslashOrDoubleQuote).collect();
+/// assert_eq!(indices, [4, 7, 9]);
+/// ```
+#[inline]
+pub fn match_indices(data: &[u8], class: ByteClass) -> MatchIndices<'_> {
+    MatchIndices { data, class, block_start: 0, next_block: 0, mask: 0 }
+}
+
+/// Iterator returned by [`match_indices`].
+#[derive(Clone, Debug)]
+pub struct MatchIndices<'a> {
+    data: &'a [u8],
+    class: ByteClass,
+    /// Offset of the block `mask` was computed for.
+    block_start: usize,
+    /// Offset of the next block to load, the block itself starts at the
+    /// next match after it.
+    next_block: usize,
+    /// Matches in the current block which haven't been yielded yet.
+    mask: u32,
+}
+
+impl Iterator for MatchIndices<'_> {
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<usize> {
+        while self.mask == 0 {
+            let rest = self.data.get(self.next_block..)?;
+            // Jump over the blocks without matches with the regular scanner,
+            // the next block starts at the next match.
+            let skip = self.class.find(rest);
+            if skip == rest.len() {
+                self.next_block = self.data.len();
+                return None;
+            }
+            self.next_block += skip;
+            let rest = &rest[skip..];
+
+            self.mask = match rest.first_chunk::<VECTOR_SIZE>() {
+                Some(block) => self.class.mask(block),
+                None => {
+                    // Pad the tail with zeros and ignore whatever they match.
+                    let mut block = [0; VECTOR_SIZE];
+                    block[..rest.len()].copy_from_slice(rest);
+                    self.class.mask(&block) & ((1 << rest.len()) - 1)
+                }
+            };
+            self.block_start = self.next_block;
+            self.next_block += VECTOR_SIZE;
+        }
+
+        let i = self.mask.trailing_zeros() as usize;
+        // Clear the lowest set bit.
+        self.mask &= self.mask - 1;
+        Some(self.block_start + i)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let pending = self.mask.count_ones() as usize;
+        let rest = self.data.len().saturating_sub(self.next_block);
+        (pending, Some(pending + rest))
+    }
+}
+
+impl core::iter::FusedIterator for MatchIndices<'_> {}
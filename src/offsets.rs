@@ -0,0 +1,208 @@
+//! Escaping with a map between offsets in the input and in the escaped output.
+//!
+//! Every input byte takes 1, 2 or 4 bytes of output, so the map only stores
+//! where the width changes. Mostly printable input needs a handful of runs
+//! regardless of its length, every `\` or `"` adds two.
+
+use crate::*;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+/// Same as [`to_ascii_or_hex_simd_v2`], but also returns the [`OffsetMap`]
+/// between `data` and the appended output. Output offsets in the map count
+/// from where the output starts, not from the start of `out`.
+///
+/// ```
+/// # use simd_rs::to_ascii_or_hex_with_offsets;
+/// let mut out = String::new();
+/// let map = to_ascii_or_hex_with_offsets(b"key=\"\x00\x01\"", &mut out);
+/// assert_eq!(out, r#"key=\"\x00\x01\""#);
+/// assert_eq!(map.to_output(5), 6);
+/// assert_eq!(map.to_input(9), 5);
+/// assert_eq!(map.output_range(5..7), 6..14);
+/// ```
+pub fn to_ascii_or_hex_with_offsets(data: &[u8], out: &mut String) -> OffsetMap {
+    let mut map = OffsetMap::default();
+    if let Some(s) = as_unescaped_str(data) {
+        out.push_str(s);
+        map.push(data.len(), 1);
+        return map;
+    }
+
+    let dummy = String::new();
+    let mut buffer = core::mem::replace(out, dummy).into_bytes();
+    buffer.reserve(escaped_len(data));
+
+    let mut recorder = OffsetRecorder { buffer, map };
+    escape::escape_with(data, &mut recorder);
+    let OffsetRecorder { buffer, map } = recorder;
+
+    // The buffer started out as a valid `String` and only printable ASCII
+    // bytes were appended to it since.
+    debug_assert!(core::str::from_utf8(&buffer).is_ok(), "{buffer:?}");
+    // SAFETY: see above.
+    *out = unsafe { String::from_utf8_unchecked(buffer) };
+    return map;
+}
+
+/// Appends the escaped output to `buffer` and records its runs in `map`.
+struct OffsetRecorder {
+    buffer: Vec<u8>,
+    map: OffsetMap,
+}
+
+impl escape::EscapeSink for OffsetRecorder {
+    #[inline(always)]
+    fn push_verbatim(&mut self, bytes: &[u8]) {
+        self.buffer.push_verbatim(bytes);
+        self.map.push(bytes.len(), 1);
+    }
+
+    #[inline(always)]
+    fn push_hex(&mut self, bytes: &[u8]) {
+        self.buffer.push_hex(bytes);
+        self.map.push(bytes.len(), 4);
+    }
+
+    #[inline(always)]
+    fn push_special(&mut self, c: u8) {
+        self.buffer.push_special(c);
+        self.map.push(1, 2);
+    }
+}
+
+/// Run-length map between input offsets and escaped output offsets, returned
+/// by [`to_ascii_or_hex_with_offsets`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct OffsetMap {
+    /// Sorted by both offsets, adjacent runs have different widths.
+    runs: Vec<Run>,
+    input_len: usize,
+    output_len: usize,
+}
+
+/// Input bytes starting at `input`, each of which takes `width` bytes of
+/// output starting at `output`. The run ends where the next one starts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Run {
+    input: usize,
+    output: usize,
+    width: usize,
+}
+
+impl OffsetMap {
+    fn push(&mut self, n: usize, width: usize) {
+        if n == 0 {
+            return;
+        }
+        if self.runs.last().map(|run| run.width) != Some(width) {
+            self.runs.push(Run { input: self.input_len, output: self.output_len, width });
+        }
+        self.input_len += n;
+        self.output_len += n * width;
+    }
+
+    #[inline]
+    pub fn input_len(&self) -> usize {
+        self.input_len
+    }
+
+    #[inline]
+    pub fn output_len(&self) -> usize {
+        self.output_len
+    }
+
+    /// Returns the number of runs the map is stored as.
+    #[inline]
+    pub fn run_count(&self) -> usize {
+        self.runs.len()
+    }
+
+    /// Returns the offset in the output where the escaped form of the input
+    /// byte at `input` starts. [`Self::input_len`] maps to
+    /// [`Self::output_len`].
+    ///
+    /// # Panics
+    /// If `input` is greater than [`Self::input_len`].
+    pub fn to_output(&self, input: usize) -> usize {
+        assert!(input <= self.input_len, "input offset {input} is out of bounds of {}", self.input_len);
+        if input == self.input_len {
+            return self.output_len;
+        }
+        let run = self.runs[self.runs.partition_point(|run| run.input <= input) - 1];
+        return run.output + (input - run.input) * run.width;
+    }
+
+    /// Returns the offset of the input byte whose escaped form contains the
+    /// output byte at `output`. [`Self::output_len`] maps to
+    /// [`Self::input_len`].
+    ///
+    /// # Panics
+    /// If `output` is greater than [`Self::output_len`].
+    pub fn to_input(&self, output: usize) -> usize {
+        assert!(output <= self.output_len, "output offset {output} is out of bounds of {}", self.output_len);
+        if output == self.output_len {
+            return self.input_len;
+        }
+        let run = self.runs[self.runs.partition_point(|run| run.output <= output) - 1];
+        return run.input + (output - run.output) / run.width;
+    }
+
+    /// Returns the part of the output which is the escaped form of `input`.
+    ///
+    /// # Panics
+    /// If `input` is out of bounds of [`Self::input_len`].
+    #[inline]
+    pub fn output_range(&self, input: Range<usize>) -> Range<usize> {
+        self.to_output(input.start)..self.to_output(input.end)
+    }
+
+    /// Returns a [`core::fmt::Display`] adapter which shows `escaped`, the
+    /// output this map was created with, with the escaped form of `input`
+    /// highlighted. An empty `input` range highlights the single byte it
+    /// starts at.
+    ///
+    /// The highlight is in reverse video, or with the alternate flag (`{:#}`)
+    /// a line of `^` under `escaped`, for output which isn't a terminal.
+    ///
+    /// ```
+    /// # use simd_rs::to_ascii_or_hex_with_offsets;
+    /// let mut escaped = String::new();
+    /// let map = to_ascii_or_hex_with_offsets(b"a=\x01\x02;", &mut escaped);
+    /// assert_eq!(format!("{:#}", map.highlight(&escaped, 3..3)), "a=\\x01\\x02;\n      ^^^^");
+    /// ```
+    ///
+    /// # Panics
+    /// If `input` is out of bounds of [`Self::input_len`], or `escaped` isn't
+    /// [`Self::output_len`] bytes long.
+    pub fn highlight<'a>(&self, escaped: &'a str, input: Range<usize>) -> Highlight<'a> {
+        assert_eq!(escaped.len(), self.output_len, "not the output of this map");
+        let end = if input.is_empty() { (input.start + 1).min(self.input_len) } else { input.end };
+        Highlight { escaped, range: self.to_output(input.start)..self.to_output(end) }
+    }
+}
+
+/// Returned by [`OffsetMap::highlight`].
+#[derive(Clone, Debug)]
+pub struct Highlight<'a> {
+    escaped: &'a str,
+    range: Range<usize>,
+}
+
+impl core::fmt::Display for Highlight<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let Range { start, end } = self.range;
+        if f.alternate() {
+            f.write_str(self.escaped)?;
+            f.write_str("\n")?;
+            for _ in 0..start { f.write_str(" ")?; }
+            for _ in start..end.max(start + 1) { f.write_str("^")?; }
+            return Ok(());
+        }
+        f.write_str(&self.escaped[..start])?;
+        f.write_str("\x1b[7m")?;
+        f.write_str(&self.escaped[start..end])?;
+        f.write_str("\x1b[27m")?;
+        f.write_str(&self.escaped[end..])
+    }
+}
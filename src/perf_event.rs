@@ -0,0 +1,229 @@
+////////////////////////////////////////////////////////////////////////////////
+// Counters
+////////////////////////////////////////////////////////////////////////////////
+
+/// Values of the hardware counters collected over a single measured region.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Sample {
+    pub instructions: u64,
+    pub cycles: u64,
+    pub branch_misses: u64,
+    pub cache_misses: u64,
+}
+
+impl std::fmt::Display for Sample {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "instructions: {}, cycles: {}, branch-misses: {}, cache-misses: {}",
+            self.instructions, self.cycles, self.branch_misses, self.cache_misses,
+        )
+    }
+}
+
+/// A group of hardware counters opened with [`perf_event_open(2)`].
+///
+/// Opening the counters never fails, if they aren't supported (the crate was
+/// built without the `perf` feature, the kernel forbids it via
+/// `perf_event_paranoid`, we're in a container, etc.) every measurement
+/// simply returns `None` and [`Counters::unavailable_reason`] says why.
+///
+/// [`perf_event_open(2)`]: https://man7.org/linux/man-pages/man2/perf_event_open.2.html
+pub struct Counters {
+    inner: Result<imp::Group, String>,
+}
+
+impl Counters {
+    pub fn open() -> Self {
+        Self { inner: imp::Group::open() }
+    }
+
+    #[inline]
+    pub fn unavailable_reason(&self) -> Option<&str> {
+        self.inner.as_ref().err().map(String::as_str)
+    }
+
+    /// Resets and enables the counters.
+    #[inline(always)]
+    pub fn start(&mut self) {
+        if let Ok(group) = &mut self.inner {
+            group.start();
+        }
+    }
+
+    /// Disables the counters and returns the values accumulated since the last
+    /// call to [`Counters::start`].
+    #[inline(always)]
+    pub fn stop(&mut self) -> Option<Sample> {
+        match &mut self.inner {
+            Ok(group) => group.stop(),
+            Err(_) => None,
+        }
+    }
+}
+
+#[cfg(all(target_os = "linux", feature = "perf"))]
+mod imp {
+    use super::Sample;
+
+    const PERF_TYPE_HARDWARE: u32 = 0;
+
+    const PERF_COUNT_HW_CPU_CYCLES: u64 = 0;
+    const PERF_COUNT_HW_INSTRUCTIONS: u64 = 1;
+    const PERF_COUNT_HW_CACHE_MISSES: u64 = 3;
+    const PERF_COUNT_HW_BRANCH_MISSES: u64 = 5;
+
+    const PERF_FORMAT_GROUP: u64 = 1 << 3;
+
+    const ATTR_FLAG_DISABLED: u64 = 1 << 0;
+    const ATTR_FLAG_EXCLUDE_KERNEL: u64 = 1 << 5;
+    const ATTR_FLAG_EXCLUDE_HV: u64 = 1 << 6;
+
+    const PERF_EVENT_IOC_ENABLE: libc::c_ulong = 0x2400;
+    const PERF_EVENT_IOC_DISABLE: libc::c_ulong = 0x2401;
+    const PERF_EVENT_IOC_RESET: libc::c_ulong = 0x2403;
+    const PERF_IOC_FLAG_GROUP: libc::c_ulong = 1;
+
+    /// Order in which the events are added to the group, which is also the
+    /// order in which their values are read back.
+    const EVENTS: [(&str, u64); 4] = [
+        ("instructions", PERF_COUNT_HW_INSTRUCTIONS),
+        ("cycles", PERF_COUNT_HW_CPU_CYCLES),
+        ("branch-misses", PERF_COUNT_HW_BRANCH_MISSES),
+        ("cache-misses", PERF_COUNT_HW_CACHE_MISSES),
+    ];
+
+    /// `struct perf_event_attr` up to `PERF_ATTR_SIZE_VER5`, the bitfield
+    /// flags are collapsed into a single `u64`.
+    #[repr(C)]
+    #[derive(Default)]
+    struct perf_event_attr {
+        type_: u32,
+        size: u32,
+        config: u64,
+        sample_period: u64,
+        sample_type: u64,
+        read_format: u64,
+        flags: u64,
+        wakeup_events: u32,
+        bp_type: u32,
+        config1: u64,
+        config2: u64,
+        branch_sample_type: u64,
+        sample_regs_user: u64,
+        sample_stack_user: u32,
+        clockid: i32,
+        sample_regs_intr: u64,
+        aux_watermark: u32,
+        sample_max_stack: u16,
+        reserved_2: u16,
+    }
+
+    pub struct Group {
+        /// File descriptors of the events, the first one is the group leader.
+        fds: Vec<libc::c_int>,
+    }
+
+    impl Group {
+        pub fn open() -> Result<Self, String> {
+            let mut group = Self { fds: Vec::with_capacity(EVENTS.len()) };
+            for (name, config) in EVENTS {
+                let mut attr = perf_event_attr {
+                    type_: PERF_TYPE_HARDWARE,
+                    size: std::mem::size_of::<perf_event_attr>() as _,
+                    config,
+                    read_format: PERF_FORMAT_GROUP,
+                    flags: ATTR_FLAG_EXCLUDE_KERNEL | ATTR_FLAG_EXCLUDE_HV,
+                    ..Default::default()
+                };
+                let group_fd = match group.fds.first() {
+                    Some(&leader) => leader,
+                    None => {
+                        attr.flags |= ATTR_FLAG_DISABLED;
+                        -1
+                    }
+                };
+
+                // SAFETY: `attr` is a valid `perf_event_attr` with `size` set
+                // correctly, the rest of the arguments are plain integers.
+                let fd = unsafe {
+                    libc::syscall(libc::SYS_perf_event_open, &attr as *const perf_event_attr, 0, -1, group_fd, 0)
+                };
+                if fd < 0 {
+                    let err = std::io::Error::last_os_error();
+                    return Err(format!("failed to open {name} counter: {err}"));
+                }
+                group.fds.push(fd as _);
+            }
+
+            return Ok(group);
+        }
+
+        #[inline(always)]
+        pub fn start(&mut self) {
+            let leader = self.fds[0];
+            // SAFETY: `leader` is an open perf event file descriptor.
+            unsafe {
+                libc::ioctl(leader, PERF_EVENT_IOC_RESET as _, PERF_IOC_FLAG_GROUP);
+                libc::ioctl(leader, PERF_EVENT_IOC_ENABLE as _, PERF_IOC_FLAG_GROUP);
+            }
+        }
+
+        #[inline(always)]
+        pub fn stop(&mut self) -> Option<Sample> {
+            let leader = self.fds[0];
+            // SAFETY: `leader` is an open perf event file descriptor.
+            unsafe {
+                libc::ioctl(leader, PERF_EVENT_IOC_DISABLE as _, PERF_IOC_FLAG_GROUP);
+            }
+
+            // PERF_FORMAT_GROUP layout: `u64 nr; u64 values[nr];`
+            let mut buffer = [0_u64; 1 + EVENTS.len()];
+            let size = std::mem::size_of_val(&buffer);
+            // SAFETY: `buffer` is valid for writes of `size` bytes.
+            let rc = unsafe { libc::read(leader, buffer.as_mut_ptr() as _, size) };
+            if rc != size as isize || buffer[0] != EVENTS.len() as u64 {
+                return None;
+            }
+
+            Some(Sample {
+                instructions: buffer[1],
+                cycles: buffer[2],
+                branch_misses: buffer[3],
+                cache_misses: buffer[4],
+            })
+        }
+    }
+
+    impl Drop for Group {
+        fn drop(&mut self) {
+            for &fd in &self.fds {
+                // SAFETY: `fd` was opened by us and is closed exactly once.
+                unsafe { libc::close(fd); }
+            }
+        }
+    }
+}
+
+#[cfg(not(all(target_os = "linux", feature = "perf")))]
+mod imp {
+    use super::Sample;
+
+    pub enum Group {}
+
+    impl Group {
+        pub fn open() -> Result<Self, String> {
+            if cfg!(target_os = "linux") {
+                Err("built without the `perf` feature".into())
+            } else {
+                Err("perf_event_open is only supported on linux".into())
+            }
+        }
+
+        pub fn start(&mut self) {
+            match *self {}
+        }
+
+        pub fn stop(&mut self) -> Option<Sample> {
+            match *self {}
+        }
+    }
+}
@@ -0,0 +1,102 @@
+//! Portable stand-ins for the handful of AVX2 intrinsics used by the kernels.
+//!
+//! Used instead of `core::arch::x86_64` when running under [Miri] (which
+//! doesn't support AVX2) or when the `force-scalar` feature is enabled. The
+//! kernels themselves stay exactly the same, so Miri and the sanitizers check
+//! the same pointer arithmetic, tail loops and `split_at` bookkeeping as in
+//! the AVX2 build.
+//!
+//! ```text
+//! MIRIFLAGS=-Zmiri-disable-isolation cargo +nightly miri test
+//! RUSTFLAGS=-Zsanitizer=address cargo +nightly test --features force-scalar --target x86_64-unknown-linux-gnu
+//! ```
+//!
+//! Isolation has to be disabled because the random tests seed themselves
+//! from the OS: getrandom(2) with the `libc` feature, `/dev/urandom`
+//! otherwise, and the system clock as the last resort. Setting `SIMD_RS_SEED`
+//! skips all of them.
+//!
+//! [Miri]: https://github.com/rust-lang/miri
+
+#![allow(non_camel_case_types)]
+
+/// 32 lanes of `i8`, same size as the real `__m256i` so that the constants in
+/// the crate root can be `transmute`d the same way.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(C, align(32))]
+pub struct __m256i([i8; 32]);
+
+#[inline(always)]
+fn lanewise(a: __m256i, b: __m256i, f: impl Fn(i8, i8) -> i8) -> __m256i {
+    let mut res = [0; 32];
+    for i in 0..32 {
+        res[i] = f(a.0[i], b.0[i]);
+    }
+    __m256i(res)
+}
+
+/// # Safety
+/// `p` must be valid for reads of 32 bytes, no alignment is required.
+#[inline(always)]
+pub unsafe fn _mm256_loadu_si256(p: *const __m256i) -> __m256i {
+    __m256i((p as *const [i8; 32]).read_unaligned())
+}
+
+/// Signed comparison, same as `vpcmpgtb`.
+#[inline(always)]
+pub fn _mm256_cmpgt_epi8(a: __m256i, b: __m256i) -> __m256i {
+    lanewise(a, b, |a, b| if a > b { -1 } else { 0 })
+}
+
+#[inline(always)]
+pub fn _mm256_cmpeq_epi8(a: __m256i, b: __m256i) -> __m256i {
+    lanewise(a, b, |a, b| if a == b { -1 } else { 0 })
+}
+
+#[inline(always)]
+pub fn _mm256_and_si256(a: __m256i, b: __m256i) -> __m256i {
+    lanewise(a, b, |a, b| a & b)
+}
+
+#[inline(always)]
+pub fn _mm256_or_si256(a: __m256i, b: __m256i) -> __m256i {
+    lanewise(a, b, |a, b| a | b)
+}
+
+#[inline(always)]
+pub fn _mm256_setzero_si256() -> __m256i {
+    __m256i([0; 32])
+}
+
+/// Wrapping lanewise subtraction, same as `vpsubb`.
+#[inline(always)]
+pub fn _mm256_sub_epi8(a: __m256i, b: __m256i) -> __m256i {
+    lanewise(a, b, |a, b| a.wrapping_sub(b))
+}
+
+/// Sums the absolute differences of the unsigned bytes in each group of 8
+/// into the low 16 bits of the corresponding `u64` lane, same as `vpsadbw`.
+#[inline(always)]
+pub fn _mm256_sad_epu8(a: __m256i, b: __m256i) -> __m256i {
+    let mut res = [0; 32];
+    for group in 0..4 {
+        let mut sum = 0_u64;
+        for i in group * 8..group * 8 + 8 {
+            sum += (a.0[i] as u8).abs_diff(b.0[i] as u8) as u64;
+        }
+        for (i, byte) in sum.to_le_bytes().into_iter().enumerate() {
+            res[group * 8 + i] = byte as i8;
+        }
+    }
+    __m256i(res)
+}
+
+/// Collects the most significant bit of each lane, same as `vpmovmskb`.
+#[inline(always)]
+pub fn _mm256_movemask_epi8(a: __m256i) -> i32 {
+    let mut mask = 0_u32;
+    for i in 0..32 {
+        mask |= ((a.0[i] as u8 >> 7) as u32) << i;
+    }
+    mask as i32
+}
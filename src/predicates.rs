@@ -0,0 +1,71 @@
+//! Yes/no questions about a whole slice, without encoding anything.
+//!
+//! Unlike `find_non_ascii_simd(data) == data.len()` these don't need to know
+//! where the first offending byte is, so the kernels combine several vectors
+//! before a single test and branch. That's what makes the common case of a
+//! clean slice cheap.
+
+use crate::*;
+
+/// Returns `true` if every byte of `data` is printable ASCII.
+#[no_mangle]
+pub fn is_printable_ascii(data: &[u8]) -> bool {
+    if has_avx2() {
+        // SAFETY: AVX2 support was checked right above.
+        unsafe { kernels::is_printable_ascii(data) }
+    } else {
+        is_printable_ascii_non_simd(data)
+    }
+}
+
+/// Returns `true` if the encoders would change `data`, i.e. it has a byte
+/// which isn't printable ASCII, or a `\` or `"`.
+#[no_mangle]
+pub fn needs_escaping(data: &[u8]) -> bool {
+    if has_avx2() {
+        // SAFETY: AVX2 support was checked right above.
+        unsafe { kernels::needs_escaping(data) }
+    } else {
+        needs_escaping_non_simd(data)
+    }
+}
+
+/// Returns `data` as a string if it's the same as its escaped form, so it
+/// can be used as is instead of being copied through an encoder.
+///
+/// ```
+/// # use simd_rs::as_unescaped_str;
+/// assert_eq!(as_unescaped_str(b"hello"), Some("hello"));
+/// assert_eq!(as_unescaped_str(b"\"hello\""), None);
+/// assert_eq!(as_unescaped_str(b"hello\n"), None);
+/// ```
+#[inline]
+pub fn as_unescaped_str(data: &[u8]) -> Option<&str> {
+    if needs_escaping(data) {
+        return None;
+    }
+    // SAFETY: printable ASCII is valid UTF-8.
+    return Some(unsafe { core::str::from_utf8_unchecked(data) });
+}
+
+#[no_mangle]
+#[inline(never)]
+pub fn is_printable_ascii_non_simd(data: &[u8]) -> bool {
+    for &c in data {
+        if c < b' ' || c > b'~' {
+            return false;
+        }
+    }
+    return true;
+}
+
+#[no_mangle]
+#[inline(never)]
+pub fn needs_escaping_non_simd(data: &[u8]) -> bool {
+    for &c in data {
+        if c < b' ' || c > b'~' || c == b'\\' || c == b'"' {
+            return true;
+        }
+    }
+    return false;
+}
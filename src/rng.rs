@@ -1,75 +1,348 @@
 ////////////////////////////////////////////////////////////////////////////////
-// random_u64
+// Lcg128
 ////////////////////////////////////////////////////////////////////////////////
 
-/// Returns a pseudo-random `u64` value.
+use alloc::vec::Vec;
+use core::ops::{Range, RangeInclusive};
+
+/// A simple [LCG] based on [this article], with explicit state.
 ///
-/// Implemented as a simple [LCG] based on [this article].
+/// The same seed always produces the same sequence, so anything generated
+/// from it can be replayed exactly. Without the `std` feature this is the only
+/// way to get random numbers and the caller is responsible for seeding it.
+/// With `std` the [`random_u64`], [`random_choice`] and [`fill_bytes`] helpers
+/// keep one generator per thread, see [`seed`].
 ///
 /// [LCG]: https://en.wikipedia.org/wiki/Linear_congruential_generator
 /// [this article]: https://www.pcg-random.org/posts/does-it-beat-the-minimal-standard.html
-pub fn random_u64() -> u64 {
-    use std::cell::Cell;
+#[derive(Clone, Debug)]
+pub struct Lcg128 {
+    state: u128,
+}
 
+impl Lcg128 {
     const FACTOR: u128 = 0x2d99787926d46932a4c1f32680f70c55;
 
-    thread_local! {
-        static STATE: Cell<Option<u128>> = Cell::new(None);
+    #[inline]
+    pub const fn from_seed(seed: u128) -> Self {
+        Self { state: seed }
+    }
+
+    /// Returns the next pseudo-random `u64` value.
+    #[inline]
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_mul(Self::FACTOR);
+        self.state = self.state.wrapping_add(Self::FACTOR);
+        return (self.state >> 64) as u64;
+    }
+
+    /// Fills `buf` with pseudo-random bytes, which are the little-endian bytes
+    /// of consecutive [`Lcg128::next_u64`] values. The last value is truncated
+    /// if `buf.len()` isn't a multiple of 8.
+    #[inline]
+    pub fn fill_bytes(&mut self, buf: &mut [u8]) {
+        for chunk in buf.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    /// Returns a uniformly distributed value in `range`.
+    ///
+    /// Uses [Lemire's method], which unlike `next_u64() % n` has no modulo
+    /// bias and only needs a division in the rare case of a rejection.
+    ///
+    /// # Panics
+    /// If `range` is empty.
+    ///
+    /// [Lemire's method]: https://arxiv.org/abs/1805.10941
+    #[inline]
+    pub fn gen_range(&mut self, range: Range<u64>) -> u64 {
+        assert!(range.start < range.end, "empty range {range:?}");
+        let n = range.end - range.start;
+        let mut m = self.next_u64() as u128 * n as u128;
+        if (m as u64) < n {
+            let threshold = n.wrapping_neg() % n;
+            while (m as u64) < threshold {
+                m = self.next_u64() as u128 * n as u128;
+            }
+        }
+        return range.start + (m >> 64) as u64;
+    }
+
+    /// Returns a pseudo-random element from `data`.
+    /// Returns `None` if `data` is empty.
+    #[inline]
+    pub fn choice<'a, T>(&mut self, data: &'a [T]) -> Option<&'a T> {
+        if data.is_empty() {
+            return None;
+        }
+        let i = self.gen_range(0..data.len() as u64) as usize;
+        Some(&data[i])
+    }
+
+    /// Shuffles `data` in place, every permutation is equally likely.
+    pub fn shuffle<T>(&mut self, data: &mut [T]) {
+        // Fisher-Yates
+        for i in (1..data.len()).rev() {
+            let j = self.gen_range(0..i as u64 + 1) as usize;
+            data.swap(i, j);
+        }
+    }
+
+    /// Fills `buf` with bytes drawn from `dist`.
+    pub fn fill_bytes_from(&mut self, dist: &ByteDistribution, buf: &mut [u8]) {
+        for b in buf {
+            *b = dist.sample(self);
+        }
     }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// ByteDistribution
+////////////////////////////////////////////////////////////////////////////////
+
+/// A weighted mix of byte ranges, e.g. 90% printable ASCII and 10% control
+/// bytes:
+///
+/// ```
+/// # use simd_rs::rng::{ByteDistribution, Lcg128};
+/// let dist = ByteDistribution::new(&[(90, b' '..=b'~'), (10, 0x00..=0x1f)]);
+/// let mut buf = [0; 64];
+/// Lcg128::from_seed(1).fill_bytes_from(&dist, &mut buf);
+/// assert!(buf.iter().all(|&b| b <= b'~'));
+/// ```
+///
+/// A range is picked according to the weights, then a byte is picked
+/// uniformly from it. Ranges may overlap.
+#[derive(Clone, Debug)]
+pub struct ByteDistribution {
+    /// Ranges with a nonzero weight, paired with the sum of the weights up to
+    /// and including this one.
+    ranges: Vec<(u64, RangeInclusive<u8>)>,
+    total: u64,
+}
 
-    let res = STATE.with(|state_cell| {
-        if state_cell.get().is_none() {
-            state_cell.set(Some(get_rng_seed()))
+impl ByteDistribution {
+    /// # Panics
+    /// If all of the weights are 0.
+    pub fn new(ranges: &[(u32, RangeInclusive<u8>)]) -> Self {
+        let mut res = Self { ranges: Vec::with_capacity(ranges.len()), total: 0 };
+        for (weight, range) in ranges {
+            if *weight == 0 || range.is_empty() { continue; }
+            res.total += *weight as u64;
+            res.ranges.push((res.total, range.clone()));
         }
-        let mut state = state_cell.get().unwrap();
-        state = state.wrapping_mul(FACTOR);
-        state = state.wrapping_add(FACTOR);
-        state_cell.set(Some(state));
-        return (state >> 64) as u64;
-    });
+        assert!(res.total > 0, "all weights are 0");
+        return res;
+    }
+
+    /// Every byte value is equally likely, same as [`Lcg128::fill_bytes`].
+    pub fn uniform() -> Self {
+        Self::new(&[(1, 0..=255)])
+    }
+
+    #[inline]
+    pub fn sample(&self, rng: &mut Lcg128) -> u8 {
+        let x = rng.gen_range(0..self.total);
+        let i = self.ranges.partition_point(|(cumulative, _)| *cumulative <= x);
+        let range = &self.ranges[i].1;
+        let (start, end) = (*range.start() as u64, *range.end() as u64);
+        return rng.gen_range(start..end + 1) as u8;
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// thread-local generator
+////////////////////////////////////////////////////////////////////////////////
+
+/// Environment variable which overrides the seed of the thread-local
+/// generators, either a decimal or a `0x`-prefixed hexadecimal number.
+pub const SEED_ENV_VAR: &str = "SIMD_RS_SEED";
 
-    return res;
+#[cfg(feature = "std")]
+std::thread_local! {
+    static SEED: core::cell::Cell<Option<u128>> = const { core::cell::Cell::new(None) };
+    static STATE: core::cell::Cell<Option<Lcg128>> = const { core::cell::Cell::new(None) };
+}
+
+/// Returns the seed of the current thread's generator.
+///
+/// The generator is seeded on first use from [`SEED_ENV_VAR`] if it's set,
+/// otherwise from the first available of `seed_from_getrandom` (with the
+/// `libc` feature), [`seed_from_dev_urandom`] and [`seed_from_system_time`].
+/// Every thread starts from the same seed if the variable is set, so e.g. each
+/// test function draws the same sequence on every run regardless of how the
+/// tests are scheduled.
+///
+/// # Panics
+/// If [`SEED_ENV_VAR`] is set but isn't a valid seed.
+#[cfg(feature = "std")]
+pub fn seed() -> u128 {
+    SEED.with(|seed_cell| {
+        if let Some(seed) = seed_cell.get() {
+            return seed;
+        }
+        let seed = initial_seed();
+        seed_cell.set(Some(seed));
+        return seed;
+    })
+}
+
+/// Restarts the current thread's generator from `seed`.
+#[cfg(feature = "std")]
+pub fn set_seed(seed: u128) {
+    SEED.with(|seed_cell| seed_cell.set(Some(seed)));
+    STATE.with(|state_cell| state_cell.set(Some(Lcg128::from_seed(seed))));
+}
+
+#[cfg(feature = "std")]
+#[inline]
+fn with_rng<R>(f: impl FnOnce(&mut Lcg128) -> R) -> R {
+    STATE.with(|state_cell| {
+        let mut rng = state_cell.take().unwrap_or_else(|| Lcg128::from_seed(seed()));
+        let res = f(&mut rng);
+        state_cell.set(Some(rng));
+        return res;
+    })
+}
+
+/// Returns a pseudo-random `u64` value from the current thread's generator.
+///
+/// See [`seed`] for how the generator is seeded.
+#[cfg(feature = "std")]
+pub fn random_u64() -> u64 {
+    with_rng(Lcg128::next_u64)
+}
+
+/// Fills `buf` with pseudo-random bytes from the current thread's generator.
+///
+/// See [`Lcg128::fill_bytes`].
+#[cfg(feature = "std")]
+pub fn fill_bytes(buf: &mut [u8]) {
+    with_rng(|rng| rng.fill_bytes(buf))
+}
+
+/// Fills `buf` with bytes drawn from `dist` using the current thread's
+/// generator.
+#[cfg(feature = "std")]
+pub fn fill_bytes_from(dist: &ByteDistribution, buf: &mut [u8]) {
+    with_rng(|rng| rng.fill_bytes_from(dist, buf))
+}
+
+/// Returns a uniformly distributed value in `range` from the current thread's
+/// generator, see [`Lcg128::gen_range`].
+#[cfg(feature = "std")]
+pub fn random_range(range: Range<u64>) -> u64 {
+    with_rng(|rng| rng.gen_range(range))
+}
+
+/// Shuffles `data` in place using the current thread's generator.
+#[cfg(feature = "std")]
+pub fn shuffle<T>(data: &mut [T]) {
+    with_rng(|rng| rng.shuffle(data))
 }
 
 /// Returns a pseudo-random element from `data`.
 /// Returns `None` if `data` is empty.
 ///
 /// See [`random_u64`] for details about pseudo-random number generator being used.
+#[cfg(feature = "std")]
 #[inline]
 pub fn random_choice<T>(data: &[T]) -> Option<&T> {
-    if data.is_empty() {
-        return None;
+    with_rng(|rng| rng.choice(data))
+}
+
+#[cfg(feature = "std")]
+fn initial_seed() -> u128 {
+    let value = std::env::var_os(SEED_ENV_VAR);
+    return seed_from_env_value(value.as_deref()).unwrap_or_else(get_rng_seed);
+}
+
+/// Returns the seed set by `value` of [`SEED_ENV_VAR`], or `None` if it's not
+/// set and the seed has to come from the OS.
+///
+/// # Panics
+/// If `value` isn't a valid seed, see [`parse_seed`]. Silently ignoring a
+/// typo would defeat the point of replaying a seed.
+#[cfg(feature = "std")]
+pub fn seed_from_env_value(value: Option<&std::ffi::OsStr>) -> Option<u128> {
+    let value = value?;
+    let seed = value.to_str().and_then(parse_seed);
+    let Some(seed) = seed else {
+        panic!("{SEED_ENV_VAR} must be a decimal or 0x-prefixed hexadecimal u128, got {value:?}");
+    };
+    return Some(seed);
+}
+
+/// Parses a seed in the format accepted by [`SEED_ENV_VAR`].
+pub fn parse_seed(s: &str) -> Option<u128> {
+    let s = s.trim();
+    match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u128::from_str_radix(hex, 16).ok(),
+        None => s.parse().ok(),
     }
-    let i = (random_u64() as usize) % data.len();
-    Some(&data[i])
 }
 
-#[cfg(feature = "libc")]
-/// Currently returns the system time in nanoseconds.
+////////////////////////////////////////////////////////////////////////////////
+// seeding
+////////////////////////////////////////////////////////////////////////////////
+
+/// The last source, [`seed_from_system_time`], can't fail.
+#[cfg(feature = "std")]
 fn get_rng_seed() -> u128 {
-    let mut timespec = std::mem::MaybeUninit::uninit();
-    // SAFETY: this is safe because types are being checked and all values are valid
-    let rc = unsafe { libc::clock_gettime(libc::CLOCK_REALTIME, timespec.as_mut_ptr()) };
-    if rc != 0 {
-        let err = std::io::Error::last_os_error();
-        eprintln!("failed to get time: {}", err);
-        if cfg!(debug_assertions) {
-            panic!("failed to get time: {}", err);
+    #[cfg(all(target_os = "linux", feature = "libc"))]
+    if let Some(seed) = seed_from_getrandom() {
+        return seed;
+    }
+    #[cfg(unix)]
+    if let Some(seed) = seed_from_dev_urandom() {
+        return seed;
+    }
+    return seed_from_system_time();
+}
+
+/// Returns a seed read with the [`getrandom(2)`] syscall, or `None` if it
+/// fails, e.g. on kernels older than 3.17 or under a seccomp filter.
+///
+/// [`getrandom(2)`]: https://man7.org/linux/man-pages/man2/getrandom.2.html
+#[cfg(all(target_os = "linux", feature = "std", feature = "libc"))]
+pub fn seed_from_getrandom() -> Option<u128> {
+    let mut buf = [0_u8; 16];
+    let mut filled = 0;
+    while filled < buf.len() {
+        let rest = &mut buf[filled..];
+        // SAFETY: `rest` is valid for writes of `rest.len()` bytes.
+        let rc = unsafe { libc::getrandom(rest.as_mut_ptr() as _, rest.len(), 0) };
+        if rc < 0 {
+            if std::io::Error::last_os_error().kind() == std::io::ErrorKind::Interrupted {
+                continue;
+            }
+            return None;
         }
-        return 0;
+        filled += rc as usize;
     }
-    // SAFETY: safe because rc != 0, therefore the value was initialized
-    let timespec = unsafe { timespec.assume_init() };
+    Some(u128::from_le_bytes(buf))
+}
 
-    const NANOS_PER_SECOND: u128 = 1_000_000_000;
+/// Returns a seed read from `/dev/urandom`, or `None` if it can't be read,
+/// e.g. in a chroot without `/dev`.
+#[cfg(all(unix, feature = "std"))]
+pub fn seed_from_dev_urandom() -> Option<u128> {
+    use std::io::Read as _;
 
-    let mut seed = timespec.tv_nsec as u128;
-    seed += (timespec.tv_sec as u128) * NANOS_PER_SECOND;
-    return seed;
+    let mut buf = [0_u8; 16];
+    std::fs::File::open("/dev/urandom").ok()?.read_exact(&mut buf).ok()?;
+    Some(u128::from_le_bytes(buf))
 }
 
-#[cfg(not(feature = "libc"))]
-fn get_rng_seed() -> u128 {
+/// Returns the system time in nanoseconds since the Unix epoch, or before it
+/// if the clock is set that far back.
+#[cfg(feature = "std")]
+pub fn seed_from_system_time() -> u128 {
     let t0 = std::time::SystemTime::now();
-    unsafe { std::mem::transmute(t0) }
+    match t0.duration_since(std::time::UNIX_EPOCH) {
+        Ok(since_epoch) => since_epoch.as_nanos(),
+        Err(e) => e.duration().as_nanos(),
+    }
 }
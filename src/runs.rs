@@ -0,0 +1,93 @@
+//! Splitting bytes into alternating runs of printable and non-printable ASCII.
+
+use crate::*;
+
+/// Kind of the bytes in a run yielded by [`AsciiRuns`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RunKind {
+    /// Bytes in `b' '..=b'~'`.
+    Printable,
+    /// Every other byte.
+    NonPrintable,
+}
+
+impl RunKind {
+    #[inline(always)]
+    pub fn of(byte: u8) -> Self {
+        if byte >= b' ' && byte <= b'~' { RunKind::Printable } else { RunKind::NonPrintable }
+    }
+}
+
+/// Iterator over the maximal runs of printable and non-printable bytes of a
+/// slice, created with [`ascii_runs`].
+///
+/// The runs are non-empty, alternate in kind and concatenate back to the
+/// original slice. Both ends use the SIMD scanners, so this is the same loop
+/// as in [`to_ascii_or_hex_simd_v2`]:
+///
+/// ```
+/// # use simd_rs::{ascii_runs, RunKind};
+/// let runs: Vec<_> = ascii_runs(b"\x00\x01abc\xff").collect();
+/// assert_eq!(runs, [
+///     (RunKind::NonPrintable, &b"\x00\x01"[..]),
+///     (RunKind::Printable, &b"abc"[..]),
+///     (RunKind::NonPrintable, &b"\xff"[..]),
+/// ]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct AsciiRuns<'a> {
+    rest: &'a [u8],
+}
+
+#[inline]
+pub fn ascii_runs(data: &[u8]) -> AsciiRuns<'_> {
+    AsciiRuns { rest: data }
+}
+
+impl<'a> AsciiRuns<'a> {
+    /// Returns the part of the slice which hasn't been yielded yet from
+    /// either end.
+    #[inline]
+    pub fn remainder(&self) -> &'a [u8] {
+        self.rest
+    }
+}
+
+impl<'a> Iterator for AsciiRuns<'a> {
+    type Item = (RunKind, &'a [u8]);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let &first = self.rest.first()?;
+        let kind = RunKind::of(first);
+        let i = match kind {
+            RunKind::Printable => find_non_ascii_simd(self.rest),
+            RunKind::NonPrintable => find_ascii_simd(self.rest),
+        };
+        let run;
+        (run, self.rest) = self.rest.split_at(i);
+        Some((kind, run))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (!self.rest.is_empty() as usize, Some(self.rest.len()))
+    }
+}
+
+impl DoubleEndedIterator for AsciiRuns<'_> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let &last = self.rest.last()?;
+        let kind = RunKind::of(last);
+        let i = match kind {
+            RunKind::Printable => rfind_non_ascii_simd(self.rest),
+            RunKind::NonPrintable => rfind_ascii_simd(self.rest),
+        };
+        let run;
+        (self.rest, run) = self.rest.split_at(i);
+        Some((kind, run))
+    }
+}
+
+impl core::iter::FusedIterator for AsciiRuns<'_> {}
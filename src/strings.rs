@@ -0,0 +1,153 @@
+//! Extraction of printable strings, the same as GNU `strings -a`.
+//!
+//! A string is a run of at least [`StringsOptions::min_len`] printable ASCII
+//! bytes or tabs, which is what `strings` considers graphic in its default
+//! 7-bit mode. The runs are found with the SIMD scanners, tabs are the only
+//! thing which has to be looked at byte by byte.
+
+use crate::*;
+
+/// Options of [`printable_strings`] and [`read_strings`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StringsOptions {
+    /// Shorter strings are skipped, same as `strings -n`.
+    pub min_len: usize,
+    /// Only report strings followed by a `0` byte, i.e. C strings.
+    pub null_terminated: bool,
+}
+
+impl Default for StringsOptions {
+    fn default() -> Self {
+        Self { min_len: 4, null_terminated: false }
+    }
+}
+
+/// Returns an iterator over the strings in `data` and their offsets.
+///
+/// ```
+/// # use simd_rs::strings::{printable_strings, StringsOptions};
+/// let data = b"\x7fELF\x02\x01\x01\x00/lib64/ld-linux.so\x00\x01ab\x00";
+/// let strings: Vec<_> = printable_strings(data, &StringsOptions::default()).collect();
+/// assert_eq!(strings, [(8, &b"/lib64/ld-linux.so"[..])]);
+/// ```
+#[inline]
+pub fn printable_strings<'a>(data: &'a [u8], options: &StringsOptions) -> PrintableStrings<'a> {
+    PrintableStrings { data, pos: 0, options: options.clone() }
+}
+
+/// Iterator returned by [`printable_strings`].
+#[derive(Clone, Debug)]
+pub struct PrintableStrings<'a> {
+    data: &'a [u8],
+    /// Everything before this offset has been looked at.
+    pos: usize,
+    options: StringsOptions,
+}
+
+impl<'a> Iterator for PrintableStrings<'a> {
+    type Item = (usize, &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let rest = &self.data[self.pos..];
+            if rest.is_empty() {
+                return None;
+            }
+
+            // Skip to the first graphic byte, a tab might come before the
+            // first printable one.
+            let i = find_ascii_simd(rest);
+            let start = rest[..i].iter().position(|&c| c == b'\t').unwrap_or(i);
+            if start == rest.len() {
+                self.pos = self.data.len();
+                return None;
+            }
+
+            let mut end = start;
+            loop {
+                end += find_non_ascii_simd(&rest[end..]);
+                if rest.get(end) != Some(&b'\t') { break; }
+                end += 1;
+            }
+
+            let offset = self.pos + start;
+            self.pos += end;
+
+            let terminator = rest.get(end);
+            if end - start < self.options.min_len { continue; }
+            if self.options.null_terminated && terminator != Some(&0) { continue; }
+            return Some((offset, &rest[start..end]));
+        }
+    }
+}
+
+impl core::iter::FusedIterator for PrintableStrings<'_> {}
+
+/// Calls `f` with every string read from `reader` and its offset, without
+/// reading the whole input into memory.
+///
+/// The input is processed in chunks, only the unfinished string at the end of
+/// a chunk is carried over into the next one. Errors from `reader` (except
+/// [`std::io::ErrorKind::Interrupted`]) and from `f` are returned as is.
+#[cfg(feature = "std")]
+pub fn read_strings<R: std::io::Read>(
+    mut reader: R,
+    options: &StringsOptions,
+    mut f: impl FnMut(u64, &[u8]) -> std::io::Result<()>,
+) -> std::io::Result<()> {
+    const CHUNK_SIZE: usize = 64 * 1024;
+
+    let mut buffer = alloc::vec![0_u8; CHUNK_SIZE];
+    // Offset of `buffer[0]` in the input.
+    let mut base = 0_u64;
+    // Bytes at the start of `buffer` carried over from the previous chunk.
+    let mut len = 0;
+
+    loop {
+        if len == buffer.len() {
+            // A single string fills up the whole buffer.
+            buffer.resize(buffer.len() * 2, 0);
+        }
+        let n = match reader.read(&mut buffer[len..]) {
+            Ok(n) => n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        let eof = n == 0;
+        let carried = len;
+        len += n;
+
+        // Everything up to and including the last non-graphic byte can be
+        // processed now, strings in there can't be extended by the next read.
+        // The carried bytes are all graphic, so only the new ones are searched.
+        let complete = match end_of_last_non_graphic(&buffer[carried..len]) {
+            _ if eof => len,
+            0 => 0,
+            i => carried + i,
+        };
+        for (offset, s) in printable_strings(&buffer[..complete], options) {
+            f(base + offset as u64, s)?;
+        }
+        if eof {
+            return Ok(());
+        }
+
+        buffer.copy_within(complete..len, 0);
+        len -= complete;
+        base += complete as u64;
+    }
+}
+
+/// Returns the index one past the last non-graphic byte of `data`, or 0 if
+/// there is none.
+#[cfg(feature = "std")]
+fn end_of_last_non_graphic(data: &[u8]) -> usize {
+    let mut i = data.len();
+    loop {
+        i = rfind_non_ascii_simd(&data[..i]);
+        if i == 0 || data[i - 1] != b'\t' {
+            return i;
+        }
+        i -= 1;
+    }
+}
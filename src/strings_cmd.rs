@@ -0,0 +1,107 @@
+////////////////////////////////////////////////////////////////////////////////
+// strings subcommand
+////////////////////////////////////////////////////////////////////////////////
+
+use simd_rs::strings::{read_strings, StringsOptions};
+use std::io::Write as _;
+
+const USAGE: &str = "\
+usage: simd-rs strings [-n MIN_LEN] [-t d|o|x] [-0] [FILE]...
+
+Prints the printable strings in each FILE (or stdin), same as `strings -a`.
+
+  -n MIN_LEN  skip strings shorter than MIN_LEN bytes (default 4)
+  -t d|o|x    print the offset of each string in decimal, octal or hex
+  -0          only print null-terminated strings
+";
+
+#[derive(Clone, Copy)]
+enum Radix {
+    Decimal,
+    Octal,
+    Hex,
+}
+
+/// Runs `simd-rs strings` with the arguments following the subcommand name,
+/// returns the exit code.
+pub fn main(args: &[String]) -> i32 {
+    let mut options = StringsOptions::default();
+    let mut radix = None;
+    let mut files = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                print!("{USAGE}");
+                return 0;
+            }
+            "-n" => {
+                let Some(min_len) = args.next().and_then(|n| n.parse().ok()).filter(|&n| n > 0) else {
+                    eprint!("simd-rs strings: -n expects a positive number\n\n{USAGE}");
+                    return 2;
+                };
+                options.min_len = min_len;
+            }
+            "-t" => {
+                radix = match args.next().map(String::as_str) {
+                    Some("d") => Some(Radix::Decimal),
+                    Some("o") => Some(Radix::Octal),
+                    Some("x") => Some(Radix::Hex),
+                    _ => {
+                        eprint!("simd-rs strings: -t expects one of d, o, x\n\n{USAGE}");
+                        return 2;
+                    }
+                };
+            }
+            "-0" => options.null_terminated = true,
+            "-" => files.push(arg.clone()),
+            _ if arg.starts_with('-') => {
+                eprint!("simd-rs strings: unknown option {arg}\n\n{USAGE}");
+                return 2;
+            }
+            _ => files.push(arg.clone()),
+        }
+    }
+    if files.is_empty() {
+        files.push("-".into());
+    }
+
+    let stdout = std::io::stdout();
+    let mut out = std::io::BufWriter::new(stdout.lock());
+    let mut print = |offset: u64, s: &[u8]| -> std::io::Result<()> {
+        // Same format as GNU strings.
+        match radix {
+            None => {}
+            Some(Radix::Decimal) => write!(out, "{offset:7} ")?,
+            Some(Radix::Octal) => write!(out, "{offset:7o} ")?,
+            Some(Radix::Hex) => write!(out, "{offset:7x} ")?,
+        }
+        out.write_all(s)?;
+        out.write_all(b"\n")
+    };
+
+    let mut exit_code = 0;
+    for file in &files {
+        let res = if file == "-" {
+            read_strings(std::io::stdin().lock(), &options, &mut print)
+        } else {
+            std::fs::File::open(file).and_then(|f| read_strings(f, &options, &mut print))
+        };
+        if let Err(e) = res {
+            if e.kind() == std::io::ErrorKind::BrokenPipe {
+                return 0;
+            }
+            eprintln!("simd-rs strings: {file}: {e}");
+            exit_code = 1;
+        }
+    }
+
+    if let Err(e) = out.flush() {
+        if e.kind() != std::io::ErrorKind::BrokenPipe {
+            eprintln!("simd-rs strings: {e}");
+            exit_code = 1;
+        }
+    }
+    return exit_code;
+}
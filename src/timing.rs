@@ -0,0 +1,150 @@
+////////////////////////////////////////////////////////////////////////////////
+// Backend
+////////////////////////////////////////////////////////////////////////////////
+
+use core::arch::x86_64::{__rdtscp, _mm_lfence, _rdtsc};
+use std::time::{Duration, Instant};
+
+/// Clock used to time the benchmark loops.
+///
+/// Both backends report elapsed wall time and the equivalent number of
+/// [TSC] cycles, so results stay comparable whichever one produced them.
+///
+/// [TSC]: https://en.wikipedia.org/wiki/Time_Stamp_Counter
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+    /// [`std::time::Instant`], cycles are derived from the calibrated TSC
+    /// frequency.
+    Instant,
+    /// Serialized `rdtsc`/`rdtscp` reads of the time stamp counter, elapsed
+    /// time is derived from the calibrated TSC frequency.
+    Tsc,
+}
+
+impl Backend {
+    /// Reads the backend from the `SIMD_RS_TIMER` environment variable
+    /// (`tsc` or `instant`). Defaults to [`Backend::Tsc`].
+    pub fn from_env() -> Self {
+        match std::env::var("SIMD_RS_TIMER").as_deref() {
+            Ok("instant") => Self::Instant,
+            Ok("tsc") | Err(_) => Self::Tsc,
+            Ok(other) => {
+                eprintln!("unknown SIMD_RS_TIMER value {other:?}, using tsc");
+                Self::Tsc
+            }
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Timer
+////////////////////////////////////////////////////////////////////////////////
+
+/// Result of a single [`Timer::measure`] call.
+#[derive(Clone, Copy, Debug)]
+pub struct Measurement {
+    pub elapsed: Duration,
+    /// Reference cycles of the time stamp counter. Note that these tick at
+    /// the nominal frequency, not the current core frequency.
+    pub cycles: f64,
+}
+
+impl Measurement {
+    #[inline]
+    pub fn cycles_per_byte(&self, n_bytes: usize) -> f64 {
+        self.cycles / n_bytes as f64
+    }
+}
+
+pub struct Timer {
+    backend: Backend,
+    tsc_hz: f64,
+}
+
+impl Timer {
+    /// Creates a timer and calibrates the TSC frequency against the monotonic
+    /// clock, which takes a few tens of milliseconds.
+    pub fn new(backend: Backend) -> Self {
+        Self { backend, tsc_hz: calibrate_tsc_hz() }
+    }
+
+    #[inline]
+    pub fn backend(&self) -> Backend {
+        self.backend
+    }
+
+    #[inline]
+    pub fn tsc_hz(&self) -> f64 {
+        self.tsc_hz
+    }
+
+    /// Runs `f` once and returns how long it took.
+    #[inline(always)]
+    pub fn measure(&self, f: impl FnOnce()) -> Measurement {
+        match self.backend {
+            Backend::Instant => {
+                let t0 = Instant::now();
+                f();
+                let elapsed = t0.elapsed();
+                let cycles = elapsed.as_secs_f64() * self.tsc_hz;
+                Measurement { elapsed, cycles }
+            }
+            Backend::Tsc => {
+                let t0 = tsc_start();
+                f();
+                let t1 = tsc_stop();
+                let cycles = t1.wrapping_sub(t0) as f64;
+                let elapsed = Duration::from_secs_f64(cycles / self.tsc_hz);
+                Measurement { elapsed, cycles }
+            }
+        }
+    }
+}
+
+/// Reads the TSC at the start of a measured region.
+///
+/// The first `lfence` waits for all preceding instructions to complete, the
+/// second one keeps the measured code from starting before `rdtsc` does.
+#[inline(always)]
+fn tsc_start() -> u64 {
+    unsafe {
+        _mm_lfence();
+        let t = _rdtsc();
+        _mm_lfence();
+        t
+    }
+}
+
+/// Reads the TSC at the end of a measured region.
+///
+/// `rdtscp` waits until all previous instructions have executed, and the
+/// following `lfence` keeps subsequent instructions from starting early.
+#[inline(always)]
+fn tsc_stop() -> u64 {
+    let mut aux = 0;
+    unsafe {
+        let t = __rdtscp(&mut aux);
+        _mm_lfence();
+        t
+    }
+}
+
+/// Estimates the TSC frequency by spinning for a fixed interval of the
+/// monotonic clock. Takes the median of a few rounds to ignore preemptions.
+fn calibrate_tsc_hz() -> f64 {
+    const ROUNDS: usize = 5;
+    const INTERVAL: Duration = Duration::from_millis(10);
+
+    let mut estimates = [0.0; ROUNDS];
+    for estimate in &mut estimates {
+        let t0 = Instant::now();
+        let c0 = tsc_start();
+        while t0.elapsed() < INTERVAL {}
+        let c1 = tsc_stop();
+        let elapsed = t0.elapsed();
+        *estimate = c1.wrapping_sub(c0) as f64 / elapsed.as_secs_f64();
+    }
+
+    estimates.sort_by(f64::total_cmp);
+    return estimates[ROUNDS / 2];
+}
@@ -0,0 +1,96 @@
+//! Checks every byte value in every lane of a vector against the scalar
+//! definition of each byte class. The SIMD kernels use signed comparisons, so
+//! values around 0x7f/0x80 are the interesting ones here.
+
+mod common;
+
+use common::*;
+use simd_rs::*;
+
+const VECTOR_SIZE: usize = 32;
+
+/// Two full vectors plus one byte, so that both vectors go through the SIMD
+/// loop rather than the scalar tail.
+const LEN: usize = 2 * VECTOR_SIZE + 1;
+
+fn is_printable(c: u8) -> bool {
+    c >= b' ' && c <= b'~'
+}
+
+type ByteClass = fn(u8) -> bool;
+
+/// Kernel, the scalar definition of the byte class it searches for and
+/// background bytes which are never part of that class.
+const KERNELS: &[(&str, Finder, ByteClass, &[u8])] = &[
+    ("find_ascii_simd", find_ascii_simd, is_printable, &[0x00, 0x1f, 0x7f, 0x80, 0xff]),
+    ("find_non_ascii_simd", find_non_ascii_simd, |c| !is_printable(c), b" a~"),
+    ("find_backslash_or_double_quote_simd", find_backslash_or_double_quote_simd, |c| c == b'\\' || c == b'"', &[0x00, b'a', 0x80, 0xdc, 0xa2]),
+];
+
+#[test]
+#[cfg_attr(miri, ignore = "too slow under miri")]
+fn every_byte_value_in_every_lane() {
+    for &(name, kernel, in_class, backgrounds) in KERNELS {
+        for &background in backgrounds {
+            assert!(!in_class(background));
+            let mut data = [background; LEN];
+            for value in 0..=255_u8 {
+                for position in 0..LEN {
+                    data[position] = value;
+                    let expected = if in_class(value) { position } else { LEN };
+                    assert_eq!(kernel(&data), expected,
+                        "{name}: {value:#04x} at {position} in {background:#04x} background",
+                    );
+                    data[position] = background;
+                }
+            }
+        }
+    }
+}
+
+#[test]
+#[cfg_attr(miri, ignore = "too slow under miri")]
+fn every_pair_of_byte_values() {
+    // The first vector is filled with `a` and the second with `b`, which
+    // catches kernels confusing a hit in one half of the mask with the other.
+    for &(name, kernel, in_class, _) in KERNELS {
+        for a in 0..=255_u8 {
+            for b in 0..=255_u8 {
+                let mut data = [a; LEN];
+                data[VECTOR_SIZE..].fill(b);
+                let expected = data.iter().position(|&c| in_class(c)).unwrap_or(LEN);
+                assert_eq!(kernel(&data), expected, "{name}: {a:#04x} then {b:#04x}");
+            }
+        }
+    }
+}
+
+#[test]
+fn all_bytes_suffixes() {
+    let all_bytes: Vec<u8> = (0..=255).collect();
+    for &(name, kernel, in_class, _) in KERNELS {
+        for start in 0..=all_bytes.len() {
+            let data = &all_bytes[start..];
+            let expected = data.iter().position(|&c| in_class(c)).unwrap_or(data.len());
+            assert_eq!(kernel(data), expected, "{name}: all bytes from {start:#04x}");
+        }
+    }
+}
+
+#[test]
+fn encoders_on_all_bytes() {
+    let all_bytes: Vec<u8> = (0..=255).collect();
+    let mut expected = String::new();
+    for &c in &all_bytes {
+        match c {
+            b'\\' | b'"' => { expected.push('\\'); expected.push(c as char); }
+            c if is_printable(c) => expected.push(c as char),
+            c => expected.push_str(&format!("\\x{c:02x}")),
+        }
+    }
+
+    assert_eq!(encode(to_ascii_or_hex, &all_bytes), expected);
+    for &(name, encoder) in ENCODERS {
+        assert_eq!(encode(encoder, &all_bytes), expected, "{name}");
+    }
+}
@@ -0,0 +1,74 @@
+/* Calls every function of the C ABI through the generated header. Compiled
+ * and run by tests/c_abi.rs. */
+
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "simd_rs.h"
+
+static int failures = 0;
+
+#define CHECK(cond)                                                     \
+    do {                                                                \
+        if (!(cond)) {                                                  \
+            fprintf(stderr, "%s:%d: check failed: %s\n",                \
+                    __FILE__, __LINE__, #cond);                         \
+            failures++;                                                 \
+        }                                                               \
+    } while (0)
+
+static void test_find(void) {
+    const uint8_t data[] = "\x01\x02\x80 hello \"world\" \\ and then some more text\xff";
+    size_t len = sizeof(data) - 1;
+
+    CHECK(simd_find_ascii(data, len) == 3);
+    CHECK(simd_find_non_ascii(data + 3, len - 3) == len - 4);
+    CHECK(simd_find_backslash_or_double_quote(data, len) == 10);
+
+    CHECK(simd_find_ascii(NULL, 0) == 0);
+    CHECK(simd_find_non_ascii(NULL, 0) == 0);
+    CHECK(simd_find_backslash_or_double_quote(NULL, 0) == 0);
+}
+
+static void test_escape(void) {
+    const uint8_t data[] = "a\"b\\c\x00\x7f\xff";
+    size_t len = sizeof(data) - 1;
+    const char expected[] = "a\\\"b\\\\c\\x00\\x7f\\xff";
+    size_t expected_len = sizeof(expected) - 1;
+
+    size_t cap = simd_escaped_len_max(len);
+    uint8_t *out = malloc(cap);
+    size_t written = 0;
+    CHECK(simd_escape(data, len, out, cap, &written) == SIMD_OK);
+    CHECK(written == expected_len);
+    CHECK(memcmp(out, expected, expected_len) == 0);
+
+    written = 0;
+    CHECK(simd_escape(data, len, out, expected_len - 1, &written) == SIMD_ERROR_BUFFER_TOO_SMALL);
+    CHECK(written == expected_len);
+
+    written = 0;
+    CHECK(simd_escape(data, len, NULL, 0, &written) == SIMD_ERROR_BUFFER_TOO_SMALL);
+    CHECK(written == expected_len);
+
+    written = 1;
+    CHECK(simd_escape(NULL, 0, NULL, 0, &written) == SIMD_OK);
+    CHECK(written == 0);
+
+    CHECK(simd_escape(data, len, out, cap, NULL) == SIMD_ERROR_NULL_POINTER);
+    CHECK(simd_escape(NULL, 1, out, cap, &written) == SIMD_ERROR_NULL_POINTER);
+
+    free(out);
+}
+
+int main(void) {
+    test_find();
+    test_escape();
+    if (failures != 0) {
+        fprintf(stderr, "%d checks failed\n", failures);
+        return 1;
+    }
+    printf("ok\n");
+    return 0;
+}
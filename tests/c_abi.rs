@@ -0,0 +1,56 @@
+//! Compiles tests/c/test_simd_rs.c with the system C compiler against the
+//! generated header, links it with the `cdylib` and runs it.
+#![cfg(all(target_os = "linux", feature = "c-header"))]
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Directory with `libsimd_rs.so`, which cargo builds next to the test binary.
+fn cdylib_dir() -> PathBuf {
+    let exe = std::env::current_exe().unwrap();
+    let deps = exe.parent().unwrap();
+    assert!(deps.join("libsimd_rs.so").exists(), "libsimd_rs.so not found in {}", deps.display());
+    deps.into()
+}
+
+/// Only the items of src/ffi.rs belong in the header, public constants from
+/// the rest of the crate used to leak into it.
+#[test]
+fn header_only_has_the_c_abi() {
+    let header = std::fs::read_to_string(Path::new(env!("OUT_DIR")).join("simd_rs.h")).unwrap();
+    for line in header.lines() {
+        if let Some(name) = line.strip_prefix("#define ") {
+            assert!(name.starts_with("SIMD_"), "{line}");
+        }
+    }
+}
+
+#[test]
+fn c_program_links_and_passes() {
+    let cc = std::env::var("CC").unwrap_or_else(|_| "cc".into());
+    if Command::new(&cc).arg("--version").output().is_err() {
+        eprintln!("skipping: no C compiler ({cc}) found");
+        return;
+    }
+
+    let source = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/c/test_simd_rs.c");
+    let include_dir = env!("OUT_DIR");
+    let lib_dir = cdylib_dir();
+    let exe = Path::new(env!("CARGO_TARGET_TMPDIR")).join("test_simd_rs");
+
+    let status = Command::new(&cc)
+        .args(["-std=c11", "-Wall", "-Wextra", "-Werror", "-o"])
+        .arg(&exe)
+        .arg(&source)
+        .arg("-I").arg(include_dir)
+        .arg("-L").arg(&lib_dir)
+        .arg("-lsimd_rs")
+        .status()
+        .unwrap();
+    assert!(status.success(), "failed to compile {}", source.display());
+
+    let output = Command::new(&exe).env("LD_LIBRARY_PATH", &lib_dir).output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{stderr}");
+    assert_eq!(output.stdout, b"ok\n");
+}
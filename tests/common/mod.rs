@@ -0,0 +1,182 @@
+#![allow(dead_code)]
+
+use simd_rs::*;
+
+pub type Finder = fn(&[u8]) -> usize;
+pub type Counter = fn(&[u8]) -> usize;
+pub type Predicate = fn(&[u8]) -> bool;
+pub type Encoder = fn(&[u8], &mut String);
+
+/// Every `find_*` kernel paired with the scalar reference it must agree with.
+pub const FINDERS: &[(&str, Finder, Finder)] = &[
+    ("find_ascii_simd", find_ascii_simd, find_ascii_non_simd),
+    ("find_ascii_non_simd_unrolled", find_ascii_non_simd_unrolled, find_ascii_non_simd),
+    ("find_non_ascii_simd", find_non_ascii_simd, find_non_ascii_non_simd),
+    ("find_backslash_or_double_quote_simd", find_backslash_or_double_quote_simd, find_backslash_or_double_quote_non_simd),
+    ("rfind_ascii_simd", rfind_ascii_simd, rfind_ascii_non_simd),
+    ("rfind_non_ascii_simd", rfind_non_ascii_simd, rfind_non_ascii_non_simd),
+];
+
+/// Every `count_*` function paired with the scalar reference it must agree with.
+pub const COUNTERS: &[(&str, Counter, Counter)] = &[
+    ("count_printable", count_printable, count_printable_non_simd),
+    ("count_non_printable", count_non_printable, count_non_printable_non_simd),
+    ("count_backslash_or_double_quote", count_backslash_or_double_quote, count_backslash_or_double_quote_non_simd),
+];
+
+/// Every predicate paired with the scalar reference it must agree with.
+pub const PREDICATES: &[(&str, Predicate, Predicate)] = &[
+    ("is_printable_ascii", is_printable_ascii, is_printable_ascii_non_simd),
+    ("needs_escaping", needs_escaping, needs_escaping_non_simd),
+];
+
+/// Every encoder, all of them must produce the same output as [`to_ascii_or_hex`].
+pub const ENCODERS: &[(&str, Encoder)] = &[
+    ("to_ascii_or_hex_simd_v1", to_ascii_or_hex_simd_v1),
+    ("to_ascii_or_hex_simd_v2", to_ascii_or_hex_simd_v2),
+    ("ffi::simd_escape", ffi_escape),
+    ("escape_cow", cow_escape),
+    ("write_escaped", vectored_escape),
+    ("to_ascii_or_hex_with_offsets", offsets_escape),
+];
+
+/// [`to_ascii_or_hex_with_offsets`] with the same signature as the other encoders.
+fn offsets_escape(data: &[u8], out: &mut String) {
+    to_ascii_or_hex_with_offsets(data, out);
+}
+
+/// [`write_escaped`] into a `Vec` with the same signature as the other encoders.
+fn vectored_escape(data: &[u8], out: &mut String) {
+    let mut buffer = Vec::new();
+    write_escaped(&mut buffer, data).unwrap();
+    out.push_str(std::str::from_utf8(&buffer).unwrap());
+}
+
+/// [`escape_cow`] with the same signature as the other encoders.
+fn cow_escape(data: &[u8], out: &mut String) {
+    out.push_str(&escape_cow(data));
+}
+
+/// [`ffi::simd_escape`] with the same signature as the other encoders.
+fn ffi_escape(data: &[u8], out: &mut String) {
+    let mut buffer = vec![0; ffi::simd_escaped_len_max(data.len())];
+    let mut written = 0;
+    let rc = unsafe { ffi::simd_escape(data.as_ptr(), data.len(), buffer.as_mut_ptr(), buffer.len(), &mut written) };
+    assert_eq!(rc, ffi::SIMD_OK);
+    buffer.truncate(written);
+    out.push_str(std::str::from_utf8(&buffer).unwrap());
+}
+
+pub fn encode(encoder: Encoder, data: &[u8]) -> String {
+    let mut out = String::new();
+    encoder(data, &mut out);
+    return out;
+}
+
+/// Buffer aligned to a vector boundary, so that `offset` in [`Aligned::slice`]
+/// is the alignment of the returned slice relative to 32 bytes.
+#[repr(C, align(32))]
+pub struct Aligned(pub [u8; 512]);
+
+impl Aligned {
+    pub fn new() -> Box<Self> {
+        Box::new(Self([0; 512]))
+    }
+
+    pub fn slice(&mut self, offset: usize, data: &[u8]) -> &[u8] {
+        let dst = &mut self.0[offset..offset + data.len()];
+        dst.copy_from_slice(data);
+        return dst;
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// shrink
+////////////////////////////////////////////////////////////////////////////////
+
+/// Returns a minimal input for which `fails` still returns `true`, starting
+/// from the failing input `data`.
+///
+/// First removes chunks of decreasing size, then replaces each remaining
+/// byte with the simplest value which still reproduces the failure.
+pub fn shrink(data: &[u8], mut fails: impl FnMut(&[u8]) -> bool) -> Vec<u8> {
+    debug_assert!(fails(data));
+    let mut current = data.to_vec();
+
+    let mut chunk = current.len().next_power_of_two();
+    while chunk > 0 {
+        let mut start = 0;
+        while start < current.len() {
+            let end = usize::min(start + chunk, current.len());
+            let mut candidate = current[..start].to_vec();
+            candidate.extend_from_slice(&current[end..]);
+            if fails(&candidate) {
+                current = candidate;
+            } else {
+                start += chunk;
+            }
+        }
+        chunk /= 2;
+    }
+
+    for i in 0..current.len() {
+        for simpler in [0, b'a'] {
+            if current[i] == simpler { break; }
+            let original = current[i];
+            current[i] = simpler;
+            if fails(&current) { break; }
+            current[i] = original;
+        }
+    }
+
+    return current;
+}
+
+/// Prints the seed of the current thread's generator. libtest only shows the
+/// output of failed tests, so a failure can be replayed by setting
+/// [`rng::SEED_ENV_VAR`] to the printed value.
+pub fn print_seed() {
+    println!("seed: {0:#x} (replay with {1}={0:#x})", rng::seed(), rng::SEED_ENV_VAR);
+}
+
+/// Number of inputs [`for_each_random_aligned_input`] checks. Miri is several
+/// orders of magnitude slower, only a few are checked there.
+pub const N_RANDOM_ALIGNED_INPUTS: usize = if cfg!(miri) { 10 } else { 10_000 };
+
+/// Prints the seed, then runs `check` on [`N_RANDOM_ALIGNED_INPUTS`] inputs from
+/// [`random_input`], each copied to a random offset from a vector boundary
+/// which is passed along for the failure message.
+pub fn for_each_random_aligned_input(max_len: usize, mut check: impl FnMut(&[u8], usize)) {
+    print_seed();
+    let mut buffer = Aligned::new();
+    for _ in 0..N_RANDOM_ALIGNED_INPUTS {
+        let data = random_input(max_len);
+        let offset = rng::random_range(0..32) as usize;
+        check(buffer.slice(offset, &data), offset);
+    }
+}
+
+/// Returns a random input of length `0..=max_len`. Bytes are drawn from a
+/// randomly chosen pool so that inputs contain long runs of a single class as
+/// well as completely random data.
+pub fn random_input(max_len: usize) -> Vec<u8> {
+    const POOLS: &[&[u8]] = &[
+        b"abc xyz~ ",
+        b"\x00\x1f\x7f\x80\xff",
+        b"a\\\"\x00",
+        b"abcdefgh\"",
+    ];
+
+    let len = rng::random_range(0..max_len as u64 + 1) as usize;
+    let mut res = Vec::with_capacity(len);
+    match rng::random_range(0..POOLS.len() as u64 + 1) {
+        0 => {
+            for _ in 0..len { res.push(rng::random_u64() as u8); }
+        }
+        pool => {
+            let pool = POOLS[pool as usize - 1];
+            for _ in 0..len { res.push(*rng::random_choice(pool).unwrap()); }
+        }
+    }
+    return res;
+}
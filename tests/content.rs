@@ -0,0 +1,136 @@
+use simd_rs::content::{Rule, SAMPLE_LEN};
+use simd_rs::*;
+use ContentKind::*;
+
+const GIT: Rule = Rule::Git;
+const RATIO: Rule = Rule::Ratio { max_binary_ratio: 0.1 };
+
+fn utf16le(s: &str) -> Vec<u8> {
+    s.encode_utf16().flat_map(u16::to_le_bytes).collect()
+}
+
+fn utf16be(s: &str) -> Vec<u8> {
+    s.encode_utf16().flat_map(u16::to_be_bytes).collect()
+}
+
+#[track_caller]
+fn check(data: &[u8], git: ContentKind, ratio: ContentKind) {
+    assert_eq!(classify_content_with(data, GIT), git, "git {data:?}");
+    assert_eq!(classify_content_with(data, RATIO), ratio, "ratio {data:?}");
+}
+
+#[test]
+fn classify_text() {
+    check(b"", Ascii, Ascii);
+    check(b"hello world", Ascii, Ascii);
+    check(b"line 1\r\n\tline 2\n\x1b[1mbold\x1b[0m\n", Ascii, Ascii);
+    check("caf\u{e9} cr\u{e8}me br\u{fb}l\u{e9}e\n".as_bytes(), Utf8Text, Utf8Text);
+    check(b"\xef\xbb\xbfhello\n", Utf8Text, Utf8Text);
+    check(b"caf\xe9 cr\xe8me br\xfbl\xe9e\n", Latin1Likely, Latin1Likely);
+    check(b"\x01\x02 a few controls among a lot of plain text\n", Ascii, Ascii);
+}
+
+#[test]
+fn classify_utf16() {
+    let text = "hello, w\u{f6}rld\n";
+    let with_bom = |bom: &[u8], data: Vec<u8>| [bom, &data].concat();
+    check(&with_bom(b"\xff\xfe", utf16le(text)), Utf16Le, Utf16Le);
+    check(&with_bom(b"\xfe\xff", utf16be(text)), Utf16Be, Utf16Be);
+    // Without a byte order mark git only sees the NULs.
+    check(&utf16le(text), Binary, Utf16Le);
+    check(&utf16be(text), Binary, Utf16Be);
+}
+
+/// A UTF-16 byte order mark in front of anything but UTF-16 text isn't enough.
+#[test]
+fn binary_after_utf16_bom() {
+    let mut random = vec![0; 2000];
+    rng::Lcg128::from_seed(1).fill_bytes(&mut random);
+    for bom in [b"\xff\xfe", b"\xfe\xff"] {
+        check(&[&bom[..], &random].concat(), Binary, Binary);
+    }
+
+    let controls: Vec<u8> = (0_u16..32).flat_map(u16::to_be_bytes).collect();
+    check(&[&b"\xfe\xff"[..], &controls].concat(), Binary, Binary);
+    check(b"\xff\xfe\x00\x00\x00\x01\x00\x02", Binary, Binary);
+
+    // UTF-32LE starts with the UTF-16LE byte order mark followed by a NUL.
+    let utf32le: Vec<u8> = "\u{feff}hello\n".chars().flat_map(|c| (c as u32).to_le_bytes()).collect();
+    assert!(utf32le.starts_with(b"\xff\xfe\x00\x00"));
+    check(&utf32le, Binary, Binary);
+
+    // A character cut in half by the end of the sample is still fine.
+    let mut data = [&b"\xff\xfe"[..], &utf16le(&"a".repeat(SAMPLE_LEN / 2 - 2))].concat();
+    data.extend_from_slice(&utf16le("\u{1f600}"));
+    check(&data, Utf16Le, Utf16Le);
+}
+
+/// Arrays of small integers have a zero in every other byte too.
+#[test]
+fn small_integers_are_not_utf16() {
+    let le: Vec<u8> = (1_u16..200).flat_map(u16::to_le_bytes).collect();
+    let be: Vec<u8> = (1_u16..200).flat_map(u16::to_be_bytes).collect();
+    check(&le, Binary, Binary);
+    check(&be, Binary, Binary);
+}
+
+#[test]
+fn verbatim_printing() {
+    assert!(can_print_verbatim(b""));
+    assert!(can_print_verbatim(b"plain \"text\" with a \\"));
+    assert!(can_print_verbatim(b"line 1\n\tline 2\n"));
+    assert!(can_print_verbatim("caf\u{e9} cr\u{e8}me\n".as_bytes()));
+
+    // Text as far as the classification goes, but not safe for a terminal.
+    let title = b"\x1b]0;pwn\x07";
+    assert_eq!(classify_content_with(title, GIT), Ascii);
+    assert!(!can_print_verbatim(title));
+    assert!(!can_print_verbatim(b"\x1b[2J\x1b[H"));
+    assert!(!can_print_verbatim(b"progress\r"));
+    assert!(!can_print_verbatim(b"\x08\x08"));
+    assert!(!can_print_verbatim("csi \u{9b}2J".as_bytes()));
+    assert!(!can_print_verbatim(b"caf\xe9"));
+}
+
+#[test]
+fn classify_binary() {
+    check(b"\x7fELF\x02\x01\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00", Binary, Binary);
+    check(b"\x01\x02\x03\x04\x05\x06", Ascii, Binary);
+    // C1 controls only count if the data isn't UTF-8.
+    check(b"\x80\x81\x82\x83\x84\x85", Latin1Likely, Binary);
+    check(b"abcd\xc2\x80\xc2\x81\xc2\x82", Utf8Text, Utf8Text);
+
+    let all_bytes: Vec<u8> = (0..=255).collect();
+    check(&all_bytes, Binary, Binary);
+
+    let lenient = Rule::Ratio { max_binary_ratio: 1.0 };
+    assert_eq!(classify_content_with(&all_bytes, lenient), Latin1Likely);
+}
+
+#[test]
+fn only_the_sample_is_looked_at() {
+    let mut data = vec![b'a'; SAMPLE_LEN];
+    data.extend_from_slice(b"\x00\x01\x02\x03\xff");
+    check(&data, Ascii, Ascii);
+
+    // A character cut in half by the end of the sample.
+    let mut data = vec![b'a'; SAMPLE_LEN - 1];
+    data.extend_from_slice("\u{e9}".as_bytes());
+    check(&data, Utf8Text, Utf8Text);
+    // But not by the end of the data.
+    data.pop();
+    check(&data, Latin1Likely, Latin1Likely);
+}
+
+#[test]
+#[cfg_attr(miri, ignore = "reads files, too slow under miri")]
+fn classify_files() {
+    let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR"));
+    let text = std::fs::read(dir.join("tests/fixtures/strings/text.txt")).unwrap();
+    assert!(classify_content(&text).is_text());
+    assert_eq!(classify_content_with(&text, GIT), classify_content(&text));
+
+    let exe = std::fs::read(std::env::current_exe().unwrap()).unwrap();
+    check(&exe, Binary, Binary);
+    assert!(!Binary.is_text());
+}
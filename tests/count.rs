@@ -0,0 +1,40 @@
+mod common;
+
+use common::*;
+use simd_rs::*;
+
+#[test]
+fn counters_random() {
+    for_each_random_aligned_input(300, |data, offset| {
+        for &(name, counter, reference) in COUNTERS {
+            assert_eq!(counter(data), reference(data), "{name} at offset {offset} on {data:?}");
+        }
+    });
+}
+
+#[test]
+fn counters_all_bytes() {
+    let all_bytes: Vec<u8> = (0..=255).collect();
+    for start in 0..=all_bytes.len() {
+        let data = &all_bytes[start..];
+        for &(name, counter, reference) in COUNTERS {
+            assert_eq!(counter(data), reference(data), "{name} from {start:#04x}");
+        }
+    }
+    assert_eq!(count_printable(&all_bytes), 95);
+    assert_eq!(count_non_printable(&all_bytes), 161);
+    assert_eq!(count_backslash_or_double_quote(&all_bytes), 2);
+}
+
+/// The per-lane byte counters must be flushed before 256 vectors of matches.
+#[test]
+#[cfg_attr(miri, ignore = "too slow under miri")]
+fn counters_dont_overflow() {
+    for len in [255 * 32, 256 * 32, 256 * 32 + 1, 1_000_003] {
+        assert_eq!(count_printable(&vec![b'a'; len]), len);
+        assert_eq!(count_non_printable(&vec![b'a'; len]), 0);
+        assert_eq!(count_non_printable(&vec![0xff; len]), len);
+        assert_eq!(count_backslash_or_double_quote(&vec![b'"'; len]), len);
+        assert_eq!(count_backslash_or_double_quote(&vec![b'\\'; len]), len);
+    }
+}
@@ -0,0 +1,129 @@
+//! Compares every SIMD kernel with its scalar reference.
+
+mod common;
+
+use common::*;
+use simd_rs::*;
+use std::ops::Range;
+
+const MAX_LEN: usize = 200;
+// Miri is several orders of magnitude slower, the exhaustive tests are skipped
+// there and only a few random inputs are checked.
+const N_RANDOM_INPUTS: usize = if cfg!(miri) { 10 } else { 20_000 };
+
+/// Bytes placed once into a uniform background, which must be found exactly
+/// at their position. Positions cycle through the special bytes to keep the
+/// test fast in debug builds.
+const SPECIAL_BYTES: &[(u8, &[u8])] = &[
+    (b'a', b"\x00\x1f\x7f\x80\xff\\\""),
+    (0x00, b" a~\x1f\x7f\x80\\"),
+    (0xff, b" a~\x7f\x80\"\\"),
+];
+
+/// Runs `check` on every length up to [`MAX_LEN`] at each alignment offset in
+/// `offsets`, with a single special byte at every position.
+fn for_each_single_special_byte(offsets: Range<usize>, mut check: impl FnMut(&[u8], usize)) {
+    let mut buffer = Aligned::new();
+    for &(background, specials) in SPECIAL_BYTES {
+        for len in 0..=MAX_LEN {
+            let mut data = vec![background; len];
+            for offset in offsets.clone() {
+                check(buffer.slice(offset, &data), offset);
+
+                for position in 0..len {
+                    data[position] = specials[(position + offset) % specials.len()];
+                    check(buffer.slice(offset, &data), offset);
+                    data[position] = background;
+                }
+            }
+        }
+    }
+}
+
+#[test]
+#[cfg_attr(miri, ignore = "too slow under miri")]
+fn finders_single_special_byte() {
+    for_each_single_special_byte(0..32, |data, offset| {
+        for &(name, kernel, reference) in FINDERS {
+            assert_eq!(kernel(data), reference(data), "{name}: offset {offset}, {data:?}");
+        }
+    });
+}
+
+#[test]
+#[cfg_attr(miri, ignore = "too slow under miri")]
+fn encoders_single_special_byte() {
+    // The encoders are built on top of the finders, which are checked at every
+    // alignment above, so a single offset keeps this test reasonably fast.
+    for_each_single_special_byte(0..1, |data, offset| {
+        let expected = encode(to_ascii_or_hex, data);
+        for &(name, encoder) in ENCODERS {
+            assert_eq!(encode(encoder, data), expected, "{name}: offset {offset}, {data:?}");
+        }
+    });
+}
+
+#[test]
+fn finders_random() {
+    print_seed();
+    let mut buffer = Aligned::new();
+    for _ in 0..N_RANDOM_INPUTS {
+        let data = random_input(MAX_LEN);
+        let offset = rng::random_u64() as usize % 32;
+        for &(name, kernel, reference) in FINDERS {
+            let mut fails = |data: &[u8]| {
+                let slice = buffer.slice(offset, data);
+                kernel(slice) != reference(slice)
+            };
+            if fails(&data) {
+                let minimal = shrink(&data, fails);
+                panic!("{name} disagrees with reference at offset {offset} on {minimal:?}");
+            }
+        }
+    }
+}
+
+#[test]
+fn encoders_random() {
+    print_seed();
+    let mut buffer = Aligned::new();
+    for _ in 0..N_RANDOM_INPUTS {
+        let data = random_input(MAX_LEN);
+        let offset = rng::random_u64() as usize % 32;
+        for &(name, encoder) in ENCODERS {
+            let mut fails = |data: &[u8]| {
+                let slice = buffer.slice(offset, data);
+                encode(encoder, slice) != encode(to_ascii_or_hex, slice)
+            };
+            if fails(&data) {
+                let minimal = shrink(&data, fails);
+                panic!("{name} disagrees with reference at offset {offset} on {minimal:?}");
+            }
+        }
+    }
+}
+
+/// The encoders append to `out` whatever its length and capacity are. The
+/// original `to_ascii_or_hex_simd_v2` reserved `capacity - 4 * len` bytes,
+/// which underflowed whenever the output had less capacity than that.
+#[test]
+fn encoders_append_to_any_output() {
+    let data = b"\x00\x01 \"quoted\" \xff".repeat(10);
+    let expected = encode(to_ascii_or_hex, &data);
+    for &(name, encoder) in ENCODERS {
+        for capacity in [0, 3, 4 * data.len(), 10 * data.len()] {
+            let mut out = String::with_capacity(capacity);
+            out.push_str("abc");
+            encoder(&data, &mut out);
+            assert_eq!(out, format!("abc{expected}"), "{name} with capacity {capacity}");
+        }
+    }
+}
+
+#[test]
+fn shrink_finds_minimal_input() {
+    // Fails whenever there are at least two double quotes.
+    let fails = |data: &[u8]| data.iter().filter(|&&c| c == b'"').count() >= 2;
+    let data = b"\xff\x00some \"quoted\" text\x80 with a tail".to_vec();
+    assert_eq!(shrink(&data, fails), b"\"\"");
+}
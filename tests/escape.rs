@@ -0,0 +1,96 @@
+mod common;
+
+use common::*;
+use simd_rs::*;
+use std::borrow::Cow;
+
+#[test]
+fn escape_cow_borrows_clean_input() {
+    for data in [&b""[..], b"hello", b"GET /index.html HTTP/1.1", &[b'a'; 1000]] {
+        let Cow::Borrowed(s) = escape_cow(data) else { panic!("{data:?} was copied") };
+        assert_eq!(s.as_ptr(), data.as_ptr());
+        assert_eq!(s.as_bytes(), data);
+    }
+}
+
+#[test]
+fn escape_cow_allocates_exactly() {
+    print_seed();
+    for _ in 0..if cfg!(miri) { 10 } else { 5_000 } {
+        let data = random_input(300);
+        let expected = encode(to_ascii_or_hex, &data);
+        assert_eq!(escaped_len(&data), expected.len(), "{data:?}");
+        match escape_cow(&data) {
+            Cow::Borrowed(s) => assert_eq!(s, expected, "{data:?}"),
+            Cow::Owned(s) => {
+                assert_eq!(s, expected, "{data:?}");
+                assert_eq!(s.capacity(), s.len(), "{data:?}");
+            }
+        }
+    }
+}
+
+#[test]
+fn escaped_segments_borrow_verbatim_runs() {
+    print_seed();
+    for _ in 0..if cfg!(miri) { 10 } else { 5_000 } {
+        let data = random_input(300);
+        let input = data.as_ptr_range();
+        let mut actual = Vec::new();
+        for segment in escaped_segments(&data) {
+            let bytes = segment.as_bytes();
+            assert!(!bytes.is_empty(), "{data:?}");
+            match segment {
+                Segment::Verbatim(b"\\") => {}
+                Segment::Verbatim(s) => assert!(input.contains(&s.as_ptr()), "{s:?} was copied from {data:?}"),
+                Segment::Hex(chunk) => assert!(chunk.as_bytes().len() <= 4 * escape::HexChunk::MAX_BYTES),
+            }
+            actual.extend_from_slice(bytes);
+        }
+        assert_eq!(actual, encode(to_ascii_or_hex, &data).into_bytes(), "{data:?}");
+    }
+}
+
+/// Accepts a few bytes from the first couple of buffers at a time and fails
+/// with `Interrupted` every other call.
+struct Stingy {
+    out: Vec<u8>,
+    calls: usize,
+}
+
+impl std::io::Write for Stingy {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.write_vectored(&[std::io::IoSlice::new(buf)])
+    }
+
+    fn write_vectored(&mut self, bufs: &[std::io::IoSlice]) -> std::io::Result<usize> {
+        self.calls += 1;
+        if self.calls & 1 == 0 {
+            return Err(std::io::ErrorKind::Interrupted.into());
+        }
+        let mut budget = rng::random_range(1..10) as usize;
+        let mut written = 0;
+        for buf in bufs.iter().take(2) {
+            let n = buf.len().min(budget);
+            self.out.extend_from_slice(&buf[..n]);
+            written += n;
+            budget -= n;
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn write_escaped_short_writes() {
+    print_seed();
+    for _ in 0..if cfg!(miri) { 10 } else { 1_000 } {
+        let data = random_input(1000);
+        let mut out = Stingy { out: Vec::new(), calls: 0 };
+        write_escaped(&mut out, &data).unwrap();
+        assert_eq!(out.out, encode(to_ascii_or_hex, &data).into_bytes(), "{data:?}");
+    }
+}
@@ -0,0 +1,126 @@
+//! Runs every kernel on slices which start or end exactly at a page boundary
+//! next to an inaccessible guard page, so that any read outside of the slice
+//! faults instead of silently passing.
+#![cfg(all(target_os = "linux", not(miri)))]
+
+mod common;
+
+use common::*;
+use simd_rs::*;
+
+/// Lengths up to this many vectors are checked.
+const MAX_LEN: usize = 8 * 32;
+
+/// A single read-write page surrounded by `PROT_NONE` guard pages.
+struct GuardedPage {
+    mapping: *mut u8,
+    page_size: usize,
+}
+
+impl GuardedPage {
+    fn new() -> Self {
+        // SAFETY: sysconf has no preconditions.
+        let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+        assert!(MAX_LEN <= page_size);
+
+        // SAFETY: anonymous private mapping, no pointers are being passed in.
+        let mapping = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                3 * page_size,
+                libc::PROT_NONE,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+                -1,
+                0,
+            )
+        };
+        assert_ne!(mapping, libc::MAP_FAILED, "{}", std::io::Error::last_os_error());
+        let mapping = mapping as *mut u8;
+
+        // SAFETY: the middle page is within the mapping created above.
+        let rc = unsafe {
+            libc::mprotect(mapping.add(page_size) as _, page_size, libc::PROT_READ | libc::PROT_WRITE)
+        };
+        assert_eq!(rc, 0, "{}", std::io::Error::last_os_error());
+
+        Self { mapping, page_size }
+    }
+
+    fn page(&mut self) -> &mut [u8] {
+        // SAFETY: the middle page is readable and writable and is borrowed
+        // mutably together with `self`.
+        unsafe { std::slice::from_raw_parts_mut(self.mapping.add(self.page_size), self.page_size) }
+    }
+
+    /// Copies `data` to the very end of the page, right before the trailing
+    /// guard page.
+    fn at_end(&mut self, data: &[u8]) -> &[u8] {
+        let page = self.page();
+        let start = page.len() - data.len();
+        page[start..].copy_from_slice(data);
+        &page[start..]
+    }
+
+    /// Copies `data` to the very start of the page, right after the leading
+    /// guard page.
+    fn at_start(&mut self, data: &[u8]) -> &[u8] {
+        let page = self.page();
+        page[..data.len()].copy_from_slice(data);
+        &page[..data.len()]
+    }
+}
+
+impl Drop for GuardedPage {
+    fn drop(&mut self) {
+        // SAFETY: unmapping exactly the region mapped in `GuardedPage::new`.
+        unsafe { libc::munmap(self.mapping as _, 3 * self.page_size); }
+    }
+}
+
+/// Inputs for which the kernels have to scan all the way to the end of the
+/// slice, plus one with a hit in the very last byte.
+fn inputs(len: usize) -> Vec<Vec<u8>> {
+    let mut res = vec![vec![b'a'; len], vec![0x00; len], vec![0xff; len]];
+    if len > 0 {
+        let mut hit_at_end = vec![b'a'; len];
+        hit_at_end[len - 1] = b'"';
+        res.push(hit_at_end);
+    }
+    return res;
+}
+
+fn check_all_kernels(data: &[u8]) {
+    for &(name, kernel, reference) in FINDERS {
+        assert_eq!(kernel(data), reference(data), "{name}: {data:?}");
+    }
+    for &(name, counter, reference) in COUNTERS {
+        assert_eq!(counter(data), reference(data), "{name}: {data:?}");
+    }
+    for &(name, predicate, reference) in PREDICATES {
+        assert_eq!(predicate(data), reference(data), "{name}: {data:?}");
+    }
+    let expected = encode(to_ascii_or_hex, data);
+    for &(name, encoder) in ENCODERS {
+        assert_eq!(encode(encoder, data), expected, "{name}: {data:?}");
+    }
+}
+
+#[test]
+fn slices_ending_at_page_boundary() {
+    let mut page = GuardedPage::new();
+    for len in 0..=MAX_LEN {
+        for data in inputs(len) {
+            check_all_kernels(page.at_end(&data));
+        }
+    }
+}
+
+#[test]
+fn slices_starting_at_page_boundary() {
+    let mut page = GuardedPage::new();
+    for len in 0..=MAX_LEN {
+        for data in inputs(len) {
+            check_all_kernels(page.at_start(&data));
+        }
+    }
+}
@@ -0,0 +1,45 @@
+mod common;
+
+use common::*;
+use simd_rs::*;
+
+#[test]
+fn byte_histogram_random() {
+    for_each_random_aligned_input(300, |data, offset| {
+        assert_eq!(byte_histogram(data), byte_histogram_non_simd(data), "offset {offset} on {data:?}");
+    });
+}
+
+#[test]
+fn byte_histogram_all_lengths() {
+    let all_bytes: Vec<u8> = (0..=255).collect();
+    for len in 0..=all_bytes.len() {
+        let data = &all_bytes[..len];
+        assert_eq!(byte_histogram(data), byte_histogram_non_simd(data), "{len}");
+    }
+    assert_eq!(byte_histogram(&[7; 1001])[7], 1001);
+}
+
+#[test]
+fn printable_ratio_of() {
+    let ratio = |data: &[u8]| printable_ratio(&byte_histogram(data));
+    assert_eq!(ratio(b""), 1.0);
+    assert_eq!(ratio(b"hello"), 1.0);
+    assert_eq!(ratio(b"\x00\x01\x02\x03"), 0.0);
+    assert_eq!(ratio(b"ab\n\xff"), 0.5);
+    let all_bytes: Vec<u8> = (0..=255).collect();
+    assert_eq!(ratio(&all_bytes), 95.0 / 256.0);
+}
+
+#[test]
+fn shannon_entropy_of() {
+    let entropy = |data: &[u8]| shannon_entropy(&byte_histogram(data));
+    assert_eq!(entropy(b""), 0.0);
+    assert_eq!(entropy(&[b'a'; 100]), 0.0);
+    assert_eq!(entropy(b"abababab"), 1.0);
+    assert_eq!(entropy(b"abcdabcd"), 2.0);
+    let all_bytes: Vec<u8> = (0..=255).collect();
+    assert_eq!(entropy(&all_bytes), 8.0);
+    let text = b"The quick brown fox jumps over the lazy dog";
+    assert!((3.0..5.0).contains(&entropy(text)), "{}", entropy(text));
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use common::*;
+use simd_rs::*;
+
+const CLASSES: [ByteClass; 3] = [ByteClass::Printable, ByteClass::NonPrintable, ByteClass::BackslashOrDoubleQuote];
+
+fn reference_indices(data: &[u8], class: ByteClass) -> Vec<usize> {
+    (0..data.len()).filter(|&i| class.contains(data[i])).collect()
+}
+
+#[test]
+fn match_indices_random() {
+    for_each_random_aligned_input(200, |data, offset| {
+        for class in CLASSES {
+            let actual: Vec<_> = match_indices(data, class).collect();
+            assert_eq!(actual, reference_indices(data, class), "{class:?} at offset {offset} on {data:?}");
+        }
+    });
+}
+
+#[test]
+#[cfg_attr(miri, ignore = "too slow under miri")]
+fn match_indices_every_byte_value_in_every_lane() {
+    // Two full blocks and a partial one.
+    const LEN: usize = 2 * 32 + 7;
+    for class in CLASSES {
+        for background in [b'a', 0x00, 0xff] {
+            for value in 0..=255_u8 {
+                for position in 0..LEN {
+                    let mut data = [background; LEN];
+                    data[position] = value;
+                    let actual: Vec<_> = match_indices(&data, class).collect();
+                    assert_eq!(actual, reference_indices(&data, class),
+                        "{class:?}: {value:#04x} at {position} in {background:#04x} background",
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn match_indices_size_hint() {
+    let data = [b'"'; 100];
+    let mut it = match_indices(&data, ByteClass::BackslashOrDoubleQuote);
+    assert_eq!(it.size_hint(), (0, Some(100)));
+    it.next();
+    assert_eq!(it.size_hint(), (31, Some(31 + 68)));
+    assert_eq!(it.count(), 99);
+    assert_eq!(match_indices(b"", ByteClass::Printable).next(), None);
+}
+
+#[test]
+fn positions() {
+    assert_eq!(position_ascii(b"\x00\x01ab"), Some(2));
+    assert_eq!(position_ascii(b"\x00\x01"), None);
+    assert_eq!(position_ascii(b""), None);
+    assert_eq!(position_non_ascii(b"ab\xff"), Some(2));
+    assert_eq!(position_non_ascii(b"ab"), None);
+    assert_eq!(position_backslash_or_double_quote(b"a\"b"), Some(1));
+    assert_eq!(position_backslash_or_double_quote(b"ab"), None);
+    assert_eq!(rposition_ascii(b"ab\x00"), Some(1));
+    assert_eq!(rposition_ascii(b"\x00"), None);
+    assert_eq!(rposition_non_ascii(b"\x00ab"), Some(0));
+    assert_eq!(rposition_non_ascii(b"ab"), None);
+    assert_eq!(rposition_non_ascii(b""), None);
+
+    for_each_random_aligned_input(200, |data, offset| {
+        let printable = |&c: &u8| ByteClass::Printable.contains(c);
+        let special = |&c: &u8| ByteClass::BackslashOrDoubleQuote.contains(c);
+        assert_eq!(position_ascii(data), data.iter().position(printable), "offset {offset} on {data:?}");
+        assert_eq!(position_non_ascii(data), data.iter().position(|c| !printable(c)), "offset {offset} on {data:?}");
+        assert_eq!(position_backslash_or_double_quote(data), data.iter().position(special), "offset {offset} on {data:?}");
+        assert_eq!(rposition_ascii(data), data.iter().rposition(printable), "offset {offset} on {data:?}");
+        assert_eq!(rposition_non_ascii(data), data.iter().rposition(|c| !printable(c)), "offset {offset} on {data:?}");
+    });
+}
@@ -0,0 +1,63 @@
+mod common;
+
+use common::*;
+use simd_rs::*;
+
+#[test]
+fn offset_map_random() {
+    print_seed();
+    for _ in 0..if cfg!(miri) { 10 } else { 2_000 } {
+        let data = random_input(100);
+        let mut out = String::from("prefix");
+        let map = to_ascii_or_hex_with_offsets(&data, &mut out);
+        let escaped = encode(to_ascii_or_hex, &data);
+        assert_eq!(out, format!("prefix{escaped}"), "{data:?}");
+        assert_eq!(map.input_len(), data.len());
+        assert_eq!(map.output_len(), escaped.len());
+
+        for i in 0..=data.len() {
+            let output = map.to_output(i);
+            assert_eq!(output, encode(to_ascii_or_hex, &data[..i]).len(), "{i} in {data:?}");
+            assert_eq!(map.to_input(output), i, "{i} in {data:?}");
+        }
+        for o in 0..=escaped.len() {
+            let i = map.to_input(o);
+            assert!(map.to_output(i) <= o, "{o} in {data:?}");
+            assert!(i == data.len() || o < map.to_output(i + 1), "{o} in {data:?}");
+        }
+    }
+}
+
+#[test]
+fn offset_map_is_run_length() {
+    let mut out = String::new();
+    let map = to_ascii_or_hex_with_offsets(&[b'a'; 10_000], &mut out);
+    assert_eq!(map.run_count(), 1);
+
+    let mut data = vec![b'a'; 10_000];
+    data[5000] = b'"';
+    data.extend_from_slice(&[0xff; 100]);
+    let map = to_ascii_or_hex_with_offsets(&data, &mut out);
+    assert_eq!(map.run_count(), 4);
+    assert_eq!(map.output_range(5000..5001), 5000..5002);
+    assert_eq!(map.output_range(10_000..10_100), 10_001..10_401);
+
+    let map = to_ascii_or_hex_with_offsets(b"", &mut out);
+    assert_eq!((map.run_count(), map.to_output(0), map.to_input(0)), (0, 0, 0));
+}
+
+#[test]
+fn offset_map_highlight() {
+    let mut escaped = String::new();
+    let map = to_ascii_or_hex_with_offsets(b"ab\"c\x00d", &mut escaped);
+    assert_eq!(escaped, r#"ab\"c\x00d"#);
+
+    let highlight = |input: std::ops::Range<usize>| {
+        let highlight = map.highlight(&escaped, input);
+        (format!("{highlight}"), format!("{highlight:#}"))
+    };
+    assert_eq!(highlight(0..1), ("\x1b[7ma\x1b[27mb\\\"c\\x00d".into(), "ab\\\"c\\x00d\n^".into()));
+    assert_eq!(highlight(2..4), ("ab\x1b[7m\\\"c\x1b[27m\\x00d".into(), "ab\\\"c\\x00d\n  ^^^".into()));
+    assert_eq!(highlight(4..4), ("ab\\\"c\x1b[7m\\x00\x1b[27md".into(), "ab\\\"c\\x00d\n     ^^^^".into()));
+    assert_eq!(highlight(6..6), ("ab\\\"c\\x00d\x1b[7m\x1b[27m".into(), "ab\\\"c\\x00d\n          ^".into()));
+}
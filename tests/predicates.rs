@@ -0,0 +1,49 @@
+mod common;
+
+use common::*;
+use simd_rs::*;
+
+#[test]
+fn predicates_random() {
+    for_each_random_aligned_input(300, |data, offset| {
+        for &(name, predicate, reference) in PREDICATES {
+            assert_eq!(predicate(data), reference(data), "{name} at offset {offset} on {data:?}");
+        }
+    });
+}
+
+/// A single offending byte at every position of clean slices of every length
+/// up to a few unrolled iterations, so that every loop of the kernels sees it.
+#[test]
+#[cfg_attr(miri, ignore = "too slow under miri")]
+fn predicates_single_bad_byte() {
+    for len in 0..=3 * 128 + 40 {
+        let clean = vec![b'a'; len];
+        assert!(is_printable_ascii(&clean), "{len}");
+        assert!(!needs_escaping(&clean), "{len}");
+        assert_eq!(as_unescaped_str(&clean), Some(std::str::from_utf8(&clean).unwrap()));
+
+        for i in 0..len {
+            for bad in [0x00, b'\n', 0x1f, 0x7f, 0x80, 0xff, b'\\', b'"'] {
+                let mut data = clean.clone();
+                data[i] = bad;
+                let printable = bad != 0x7f && (b' '..=b'~').contains(&bad);
+                assert_eq!(is_printable_ascii(&data), printable, "{bad:#04x} at {i} of {len}");
+                assert!(needs_escaping(&data), "{bad:#04x} at {i} of {len}");
+                assert_eq!(as_unescaped_str(&data), None, "{bad:#04x} at {i} of {len}");
+            }
+        }
+    }
+}
+
+#[test]
+fn predicates_all_bytes() {
+    for c in 0..=255_u8 {
+        let data = [c; 200];
+        for &(name, predicate, reference) in PREDICATES {
+            assert_eq!(predicate(&data), reference(&data), "{name} on {c:#04x}");
+        }
+    }
+    assert!(is_printable_ascii(b""));
+    assert!(!needs_escaping(b""));
+}
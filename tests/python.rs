@@ -0,0 +1,29 @@
+//! Runs the unit tests of the ctypes bindings in python/ against the `cdylib`
+//! built for this test run.
+#![cfg(target_os = "linux")]
+
+use std::path::Path;
+use std::process::Command;
+
+#[test]
+fn python_bindings() {
+    if Command::new("python3").arg("--version").output().is_err() {
+        eprintln!("skipping: python3 not found");
+        return;
+    }
+
+    let exe = std::env::current_exe().unwrap();
+    let lib = exe.parent().unwrap().join("libsimd_rs.so");
+    assert!(lib.exists(), "{} not found", lib.display());
+
+    let python_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("python");
+    let output = Command::new("python3")
+        .args(["-m", "unittest", "-v", "test_simd_rs"])
+        .current_dir(&python_dir)
+        .env("SIMD_RS_LIB", &lib)
+        .env("PYTHONDONTWRITEBYTECODE", "1")
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{stderr}");
+}
@@ -0,0 +1,54 @@
+mod common;
+
+use common::*;
+use simd_rs::*;
+
+/// Straightforward byte by byte version of [`ascii_runs`].
+fn reference_runs(data: &[u8]) -> Vec<(RunKind, &[u8])> {
+    let mut res: Vec<(RunKind, &[u8])> = Vec::new();
+    let mut start = 0;
+    for i in 1..=data.len() {
+        if i == data.len() || RunKind::of(data[i]) != RunKind::of(data[start]) {
+            res.push((RunKind::of(data[start]), &data[start..i]));
+            start = i;
+        }
+    }
+    return res;
+}
+
+#[test]
+fn empty() {
+    assert_eq!(ascii_runs(b"").next(), None);
+    assert_eq!(ascii_runs(b"").next_back(), None);
+}
+
+#[test]
+fn matches_reference() {
+    for_each_random_aligned_input(200, |data, offset| {
+        let expected = reference_runs(data);
+
+        let forward: Vec<_> = ascii_runs(data).collect();
+        assert_eq!(forward, expected, "forward at offset {offset} on {data:?}");
+
+        let mut backward: Vec<_> = ascii_runs(data).rev().collect();
+        backward.reverse();
+        assert_eq!(backward, expected, "backward at offset {offset} on {data:?}");
+
+        // Take runs from both ends in a random order, they must meet in the
+        // middle without losing or duplicating anything.
+        let mut runs = ascii_runs(data);
+        let (mut front, mut back) = (Vec::new(), Vec::new());
+        loop {
+            if rng::random_u64() & 1 == 0 {
+                let Some(run) = runs.next() else { break };
+                front.push(run);
+            } else {
+                let Some(run) = runs.next_back() else { break };
+                back.push(run);
+            }
+        }
+        front.extend(back.into_iter().rev());
+        assert_eq!(front, expected, "both ends at offset {offset} on {data:?}");
+        assert!(runs.remainder().is_empty());
+    });
+}
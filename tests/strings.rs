@@ -0,0 +1,146 @@
+//! `simd-rs strings` against GNU `strings -a` on the fixtures in
+//! tests/fixtures/strings, and the library against a byte by byte reference.
+
+use simd_rs::rng::{self, ByteDistribution};
+use simd_rs::strings::*;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+mod common;
+use common::print_seed;
+
+fn fixtures() -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/strings");
+    let mut res: Vec<_> = std::fs::read_dir(dir).unwrap().map(|e| e.unwrap().path()).collect();
+    res.sort();
+    assert!(!res.is_empty());
+    return res;
+}
+
+fn run(program: &str, args: &[&str], file: &Path) -> Vec<u8> {
+    let output = Command::new(program).args(args).arg(file).output().unwrap();
+    assert!(output.status.success(), "{program} {args:?} {}: {output:?}", file.display());
+    return output.stdout;
+}
+
+fn compare_with_gnu_strings(file: &Path, args: &[&str]) {
+    let expected = run("strings", &[&["-a"], args].concat(), file);
+    let actual = run(env!("CARGO_BIN_EXE_simd-rs"), &[&["strings"], args].concat(), file);
+    if actual != expected {
+        let expected = String::from_utf8_lossy(&expected);
+        let actual = String::from_utf8_lossy(&actual);
+        let diff = expected.lines().zip(actual.lines()).position(|(a, b)| a != b);
+        panic!("{args:?} {}: outputs differ at line {diff:?}\nexpected:\n{expected}\nactual:\n{actual}", file.display());
+    }
+}
+
+fn has_gnu_strings() -> bool {
+    let Ok(output) = Command::new("strings").arg("--version").output() else { return false };
+    return String::from_utf8_lossy(&output.stdout).contains("GNU");
+}
+
+#[test]
+#[cfg_attr(miri, ignore = "can't spawn processes under miri")]
+fn same_as_gnu_strings_on_fixtures() {
+    if !has_gnu_strings() {
+        eprintln!("skipping: GNU strings not found");
+        return;
+    }
+
+    for file in fixtures() {
+        for min_len in ["1", "2", "4", "10"] {
+            compare_with_gnu_strings(&file, &["-n", min_len]);
+        }
+        for radix in ["d", "o", "x"] {
+            compare_with_gnu_strings(&file, &["-t", radix]);
+        }
+    }
+}
+
+#[test]
+#[cfg_attr(miri, ignore = "can't spawn processes under miri")]
+fn same_as_gnu_strings_on_an_executable() {
+    if !has_gnu_strings() {
+        eprintln!("skipping: GNU strings not found");
+        return;
+    }
+
+    // Several megabytes of real machine code and debug info, which is much
+    // larger than a single chunk of `read_strings`.
+    let exe = std::env::current_exe().unwrap();
+    compare_with_gnu_strings(&exe, &["-t", "x"]);
+    compare_with_gnu_strings(&exe, &["-n", "8"]);
+}
+
+/// Straightforward byte by byte version of [`printable_strings`].
+fn reference_strings(data: &[u8], options: &StringsOptions) -> Vec<(usize, Vec<u8>)> {
+    let is_graphic = |c: u8| c == b'\t' || (b' '..=b'~').contains(&c);
+    let mut res = Vec::new();
+    let mut start = 0;
+    for i in 0..=data.len() {
+        if i < data.len() && is_graphic(data[i]) { continue; }
+        let terminated = data.get(i) == Some(&0);
+        if i - start >= options.min_len && (terminated || !options.null_terminated) {
+            res.push((start, data[start..i].to_vec()));
+        }
+        start = i + 1;
+    }
+    return res;
+}
+
+/// Hands out the data a few bytes at a time, to have chunk boundaries
+/// everywhere.
+struct Trickle<'a>(&'a [u8]);
+
+impl std::io::Read for Trickle<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = (rng::random_u64() as usize % 13 + 1).min(buf.len()).min(self.0.len());
+        buf[..n].copy_from_slice(&self.0[..n]);
+        self.0 = &self.0[n..];
+        Ok(n)
+    }
+}
+
+fn check(data: &[u8], options: &StringsOptions) {
+    let expected = reference_strings(data, options);
+
+    let actual: Vec<_> = printable_strings(data, options).map(|(i, s)| (i, s.to_vec())).collect();
+    assert_eq!(actual, expected, "{options:?} {data:?}");
+
+    let mut actual = Vec::new();
+    read_strings(Trickle(data), options, |i, s| {
+        actual.push((i as usize, s.to_vec()));
+        Ok(())
+    }).unwrap();
+    assert_eq!(actual, expected, "read_strings {options:?} {data:?}");
+}
+
+#[test]
+fn matches_reference() {
+    print_seed();
+    let dist = ByteDistribution::new(&[
+        (60, b' '..=b'~'),
+        (10, b'\t'..=b'\t'),
+        (10, 0x00..=0x00),
+        (20, 0x01..=0xff),
+    ]);
+    for _ in 0..if cfg!(miri) { 10 } else { 2_000 } {
+        let len = rng::random_range(0..300) as usize;
+        let mut data = vec![0; len];
+        rng::fill_bytes_from(&dist, &mut data);
+        for min_len in [1, 4] {
+            for null_terminated in [false, true] {
+                check(&data, &StringsOptions { min_len, null_terminated });
+            }
+        }
+    }
+}
+
+#[test]
+fn longer_than_a_chunk() {
+    let mut data = vec![b'a'; 200_000];
+    data.extend_from_slice(b"\x00\tbc\tdef\x01");
+    data.extend(std::iter::repeat_n(b'\t', 100_000));
+    check(&data, &StringsOptions::default());
+    check(&data, &StringsOptions { min_len: 1, null_terminated: true });
+}